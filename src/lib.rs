@@ -8,4 +8,6 @@
 mod transformer;
 mod utils;
 
-pub use transformer::{DomExpressionsTransform, DomExpressionsTransformOptions, ModuleFormat};
+pub use transformer::{
+    DomExpressionsTransform, DomExpressionsTransformOptions, GenerateMode, ModuleFormat, Renderer,
+};
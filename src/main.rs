@@ -14,6 +14,7 @@ fn main() {
         eprintln!("  --dev              Enable development mode");
         eprintln!("  --hydratable       Enable hydratable mode");
         eprintln!("  --cjs              Use CommonJS module format");
+        eprintln!("  --module-name <m>  Module to import runtime helpers from (default: r-dom)");
         eprintln!("  --output <file>    Output file (default: stdout)");
         std::process::exit(1);
     }
@@ -36,6 +37,15 @@ fn main() {
                 // Note: ModuleFormat might be used in the future for output generation
                 // For now, we'll just set a flag that could influence template generation
             }
+            "--module-name" => {
+                if i + 1 < args.len() {
+                    options.module_name = args[i + 1].clone();
+                    i += 1;
+                } else {
+                    eprintln!("Error: --module-name requires a module name");
+                    std::process::exit(1);
+                }
+            }
             "--output" => {
                 if i + 1 < args.len() {
                     output_file = Some(args[i + 1].clone());
@@ -86,56 +96,10 @@ fn main() {
     }
 }
 
-/// Generate dom-expressions compatible code with custom formatting
+/// Generate dom-expressions compatible code
 fn generate_dom_expressions_code(program: &oxc_ast::ast::Program) -> String {
     use oxc_codegen::Codegen;
-    let codegen = Codegen::new();
-    let mut code = codegen.build(program).code;
-
-    // Post-process the generated code for dom-expressions compatibility
-    code = post_process_dom_expressions_code(code);
-
-    code
-}
-
-/// Post-process generated code to match dom-expressions format
-fn post_process_dom_expressions_code(mut code: String) -> String {
-    // 1. Determine required imports based on generated code
-    let mut imports = vec!["template as _$template"];
-
-    if code.contains("_$setAttribute") {
-        imports.push("setAttribute as _$setAttribute");
-    }
-    if code.contains("_$effect") {
-        imports.push("effect as _$effect");
-    }
-    if code.contains("_$style") {
-        imports.push("style as _$style");
-    }
-    if code.contains("_$classList") {
-        imports.push("classList as _$classList");
-    }
-
-    // Create import statement
-    let import_line = format!("import {{ {} }} from \"r-dom\";\n", imports.join(", "));
-    code = format!("{}{}", import_line, code);
-
-    // 2. Convert string literals to template literals for template declarations
-    // Pattern: var _tmpl$ = _$template("..."); -> var _tmpl$ = /*#__PURE__*/ _$template(`...`);
-    let template_pattern =
-        regex::Regex::new(r#"var (_tmpl\$\d*) = _\$template\("([^"]*)"\);"#).unwrap();
-    code = template_pattern
-        .replace_all(&code, |caps: &regex::Captures| {
-            let var_name = &caps[1];
-            let template_content = &caps[2];
-            format!(
-                "var {} = /*#__PURE__*/ _$template(`{}`);",
-                var_name, template_content
-            )
-        })
-        .to_string();
-
-    code
+    Codegen::new().build(program).code
 }
 
 fn transform_code(code: &str, options: &DomExpressionsTransformOptions) -> Result<String, String> {
@@ -160,6 +124,9 @@ fn transform_code(code: &str, options: &DomExpressionsTransformOptions) -> Resul
     // Transform the AST
     let mut transformer = DomExpressionsTransform::new(options, &allocator);
     transformer.transform_program(&mut program);
+    if !transformer.errors().is_empty() {
+        return Err(transformer.errors().join("\n"));
+    }
 
     // Generate code with custom formatting for dom-expressions
     let code = generate_dom_expressions_code(&program);
@@ -1,12 +1,18 @@
 /// Component transformation logic for dom-expressions
 ///
-/// This module handles dom-expressions specific components:
-/// - <Show> conditionals
-/// - <For> loops  
-/// - <Switch>/<Match> conditionals
-/// - <Suspense> boundaries
-/// - Custom components
-use oxc_ast::ast::{Expression, JSXElement, JSXElementName};
+/// Control flow like <Show>, <For> and <Switch> needs no special handling:
+/// every capitalized or dotted tag compiles to a `_$createComponent` call.
+use crate::transformer::jsx::{JSXTransformer, TransformError};
+use crate::utils::ast_utils::AstUtils;
+use crate::utils::template::TemplateGenerator;
+use oxc_allocator::TakeIn;
+use oxc_ast::ast::{
+    ArrayExpressionElement, Class, Expression, Function, JSXAttributeItem, JSXAttributeName,
+    JSXAttributeValue, JSXChild, JSXElement, JSXMemberExpressionObject, ObjectPropertyKind, Span,
+    Statement,
+};
+use oxc_ast_visit::{walk_mut, VisitMut};
+use oxc_syntax::scope::ScopeFlags;
 
 pub struct ComponentTransformer;
 
@@ -15,135 +21,307 @@ impl ComponentTransformer {
         Self
     }
 
-    /// Check if a JSX element is a dom-expressions control flow component
-    pub fn is_dom_expressions_component(name: &JSXElementName) -> bool {
-        match name {
-            JSXElementName::Identifier(ident) => {
-                matches!(
-                    ident.name.as_str(),
-                    "Show" | "For" | "Switch" | "Match" | "Suspense" | "Portal" | "Dynamic"
-                )
-            }
-            JSXElementName::IdentifierReference(ident) => {
-                matches!(
-                    ident.name.as_str(),
-                    "Show" | "For" | "Switch" | "Match" | "Suspense" | "Portal" | "Dynamic"
-                )
+    /// Transform custom (user-defined) components
+    ///
+    /// `<MyComponent prop={value}>{children}</MyComponent>` becomes
+    /// `_$createComponent(MyComponent, { prop: value, children: ... })`. Props
+    /// that read reactive state become getters so the component sees updates.
+    pub fn transform_custom_component<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &mut JSXElement<'a>,
+    ) -> Result<Expression<'a>, TransformError> {
+        let component_name = AstUtils::get_element_name(element)
+            .ok_or_else(|| ComponentError::UnsupportedComponent("unnamed component".to_string()))?;
+        let has_children = !element.children.is_empty();
+
+        // Getters and thunks built for the props bind their own `this`, so the
+        // enclosing one is declared as `_self$` ahead of the current statement
+        let mut replacer = ThisReplacer { jsx, alias: None };
+        for attr in &mut element.opening_element.attributes {
+            replacer.visit_jsx_attribute_item(attr);
+        }
+        for child in &mut element.children {
+            replacer.visit_jsx_child(child);
+        }
+        if let Some(alias) = replacer.alias {
+            jsx.this_aliases.push(alias);
+        }
+
+        // Spreads split the props into segments that `_$mergeProps` combines
+        let mut segments = Vec::new();
+        let mut dynamic_spread = false;
+        let mut properties = jsx.ast_builder.vec();
+        for attr in &mut element.opening_element.attributes {
+            let attr = match attr {
+                JSXAttributeItem::Attribute(attr) => attr,
+                JSXAttributeItem::SpreadAttribute(spread) => {
+                    if !properties.is_empty() {
+                        let properties = std::mem::replace(&mut properties, jsx.ast_builder.vec());
+                        segments.push(
+                            jsx.ast_builder
+                                .expression_object(Span::default(), properties),
+                        );
+                    }
+                    let argument = spread.argument.take_in(jsx.allocator);
+                    if AstUtils::is_dynamic_expression(&argument, true) {
+                        dynamic_spread = true;
+                        segments.push(jsx.create_dynamic_spread(argument));
+                    } else {
+                        segments.push(argument);
+                    }
+                    continue;
+                }
+            };
+            let name = match &attr.name {
+                JSXAttributeName::Identifier(ident) => ident.name.to_string(),
+                JSXAttributeName::NamespacedName(name) => {
+                    format!("{}:{}", name.namespace.name, name.name.name)
+                }
+            };
+            if has_children && name == "children" {
+                continue;
             }
-            _ => false,
+
+            let property = match &mut attr.value {
+                None => {
+                    let value = jsx
+                        .ast_builder
+                        .expression_boolean_literal(Span::default(), true);
+                    jsx.create_property(&name, value)
+                }
+                Some(JSXAttributeValue::StringLiteral(lit)) => {
+                    let value = self
+                        .create_string(jsx, &TemplateGenerator::decode_html_entities(&lit.value));
+                    jsx.create_property(&name, value)
+                }
+                Some(JSXAttributeValue::ExpressionContainer(expr_container)) => {
+                    let Some(mut expression) = jsx.take_expression(expr_container) else {
+                        continue;
+                    };
+                    if AstUtils::is_dynamic_expression_with_tags(&expression, true, true) {
+                        jsx.transform_condition(&mut expression);
+                        let body = self.return_statements(jsx, expression);
+                        jsx.create_getter(&name, body)
+                    } else {
+                        jsx.create_property(&name, expression)
+                    }
+                }
+                Some(JSXAttributeValue::Element(value)) => {
+                    // Compiled when the generated props object is visited
+                    let value = Expression::JSXElement(value.take_in_box(jsx.allocator));
+                    let body = self.return_statements(jsx, value);
+                    jsx.create_getter(&name, body)
+                }
+                Some(JSXAttributeValue::Fragment(value)) => {
+                    let value = Expression::JSXFragment(value.take_in_box(jsx.allocator));
+                    let body = self.return_statements(jsx, value);
+                    jsx.create_getter(&name, body)
+                }
+            };
+            properties.push(property);
         }
-    }
 
-    /// Transform dom-expressions control flow components
-    pub fn transform_dom_expressions_component(
-        &self,
-        element: &JSXElement,
-    ) -> Result<Expression, ComponentError> {
-        let component_name = self.get_component_name(element)?;
-
-        match component_name.as_str() {
-            "Show" => self.transform_show_component(element),
-            "For" => self.transform_for_component(element),
-            "Switch" => self.transform_switch_component(element),
-            "Match" => self.transform_match_component(element),
-            "Suspense" => self.transform_suspense_component(element),
-            "Portal" => self.transform_portal_component(element),
-            "Dynamic" => self.transform_dynamic_component(element),
-            _ => Err(ComponentError::UnsupportedComponent(component_name)),
+        if let Some(children) = self.transform_component_children(jsx, &mut element.children)? {
+            properties.push(children);
         }
+
+        if !properties.is_empty() || segments.is_empty() {
+            segments.push(
+                jsx.ast_builder
+                    .expression_object(Span::default(), properties),
+            );
+        }
+        let props = if segments.len() > 1 || dynamic_spread {
+            jsx.register_import("mergeProps");
+            jsx.create_call("_$mergeProps", segments)
+        } else {
+            segments.remove(0)
+        };
+        jsx.register_import("createComponent");
+        let component = jsx.create_member_path(&component_name);
+        Ok(jsx.create_call("_$createComponent", vec![component, props]))
     }
 
-    /// Transform <Show when={condition}>{children}</Show>
-    fn transform_show_component(
+    /// Build the `children` prop from a component's JSX children
+    ///
+    /// A single static child is passed as is. Elements and dynamic expressions
+    /// are wrapped in a getter so they are created when the component reads
+    /// them, and several children become an array with dynamic entries memoized
+    /// (or plain thunks without `memo_wrapper`).
+    fn transform_component_children<'a>(
         &self,
-        _element: &JSXElement,
-    ) -> Result<Expression, ComponentError> {
-        // TODO: Transform Show component
-        // <Show when={condition} fallback={fallback}>{children}</Show>
-        // -> (() => condition ? children : fallback)()
-        Err(ComponentError::NotImplemented("Show component"))
-    }
+        jsx: &mut JSXTransformer<'a>,
+        children: &mut oxc_allocator::Vec<'a, JSXChild<'a>>,
+    ) -> Result<Option<ObjectPropertyKind<'a>>, TransformError> {
+        // (value, is dynamic, is an element)
+        let mut values: Vec<(Expression<'a>, bool, bool)> = Vec::new();
+        for child in children.iter_mut() {
+            if !JSXTransformer::is_significant_child(child) {
+                continue;
+            }
+            match child {
+                JSXChild::Text(text) => {
+                    let raw = text.raw.unwrap_or(text.value);
+                    let value = TemplateGenerator::decode_html_entities(
+                        &TemplateGenerator::trim_whitespace(&raw),
+                    );
+                    if !value.is_empty() {
+                        values.push((self.create_string(jsx, &value), false, false));
+                    }
+                }
+                JSXChild::Element(element) => {
+                    values.push((jsx.transform_jsx_element(element)?, false, true));
+                }
+                JSXChild::ExpressionContainer(expr_container) => {
+                    let static_value = expr_container
+                        .expression
+                        .as_expression()
+                        .and_then(AstUtils::get_static_value);
+                    if let Some(value) = static_value {
+                        values.push((self.create_string(jsx, &value), false, false));
+                        continue;
+                    }
+                    let Some(mut expression) = jsx.take_expression(expr_container) else {
+                        continue;
+                    };
+                    let dynamic =
+                        AstUtils::is_dynamic_expression_with_tags(&expression, true, true);
+                    if dynamic {
+                        jsx.transform_condition(&mut expression);
+                    }
+                    values.push((expression, dynamic, false));
+                }
+                JSXChild::Fragment(fragment) => {
+                    // Compiled when the getter is visited
+                    let value = Expression::JSXFragment(fragment.take_in_box(jsx.allocator));
+                    values.push((value, false, true));
+                }
+                _ => {}
+            }
+        }
 
-    /// Transform <For each={items}>{(item) => JSX}</For>
-    fn transform_for_component(&self, _element: &JSXElement) -> Result<Expression, ComponentError> {
-        // TODO: Transform For component
-        // <For each={items}>{(item, index) => <div>{item}</div>}</For>
-        // -> createComponent(For, { each: items, children: (item, index) => ... })
-        Err(ComponentError::NotImplemented("For component"))
-    }
+        if values.len() > 1 {
+            let mut elements = jsx.ast_builder.vec();
+            for (value, dynamic, _) in values {
+                let value = if dynamic {
+                    let thunk = jsx.create_arrow(&[], value);
+                    if jsx.options.memo_wrapper {
+                        jsx.register_import("memo");
+                        jsx.create_call("_$memo", vec![thunk])
+                    } else {
+                        thunk
+                    }
+                } else {
+                    value
+                };
+                elements.push(ArrayExpressionElement::from(value));
+            }
+            let array = jsx.ast_builder.expression_array(Span::default(), elements);
+            let body = self.return_statements(jsx, array);
+            return Ok(Some(jsx.create_getter("children", body)));
+        }
 
-    /// Transform <Switch>...</Switch>
-    fn transform_switch_component(
-        &self,
-        _element: &JSXElement,
-    ) -> Result<Expression, ComponentError> {
-        // TODO: Transform Switch component
-        Err(ComponentError::NotImplemented("Switch component"))
+        let Some((value, dynamic, is_element)) = values.pop() else {
+            return Ok(None);
+        };
+        if is_element || dynamic {
+            let body = self.return_statements(jsx, value);
+            Ok(Some(jsx.create_getter("children", body)))
+        } else {
+            Ok(Some(jsx.create_property("children", value)))
+        }
     }
 
-    /// Transform <Match when={condition}>...</Match>
-    fn transform_match_component(
+    /// Getter body returning `value`
+    ///
+    /// An IIFE like `(() => { ...; return _el$; })()` is unwrapped so its
+    /// statements become the getter body directly, and `(() => expr)()` becomes
+    /// `return expr`. Only a plain, parameterless arrow can be unwrapped;
+    /// defaults or `async` would change its meaning.
+    fn return_statements<'a>(
         &self,
-        _element: &JSXElement,
-    ) -> Result<Expression, ComponentError> {
-        // TODO: Transform Match component
-        Err(ComponentError::NotImplemented("Match component"))
+        jsx: &JSXTransformer<'a>,
+        mut value: Expression<'a>,
+    ) -> oxc_allocator::Vec<'a, Statement<'a>> {
+        if let Expression::CallExpression(call) = value.without_parentheses_mut() {
+            if let (true, Expression::ArrowFunctionExpression(arrow)) = (
+                call.arguments.is_empty(),
+                call.callee.without_parentheses_mut(),
+            ) {
+                if !arrow.r#async && arrow.params.items.is_empty() && arrow.params.rest.is_none() {
+                    let mut statements = arrow.body.statements.take_in(jsx.allocator);
+                    if arrow.expression {
+                        if let Some(Statement::ExpressionStatement(statement)) = statements.pop() {
+                            let value = statement.unbox().expression;
+                            statements.push(
+                                jsx.ast_builder
+                                    .statement_return(Span::default(), Some(value)),
+                            );
+                        }
+                    }
+                    return statements;
+                }
+            }
+        }
+        jsx.ast_builder.vec1(
+            jsx.ast_builder
+                .statement_return(Span::default(), Some(value)),
+        )
     }
 
-    /// Transform <Suspense fallback={fallback}>...</Suspense>
-    fn transform_suspense_component(
-        &self,
-        _element: &JSXElement,
-    ) -> Result<Expression, ComponentError> {
-        // TODO: Transform Suspense component
-        Err(ComponentError::NotImplemented("Suspense component"))
+    fn create_string<'a>(&self, jsx: &JSXTransformer<'a>, value: &str) -> Expression<'a> {
+        jsx.ast_builder.expression_string_literal(
+            Span::default(),
+            jsx.ast_builder.atom(value),
+            None,
+        )
     }
+}
 
-    /// Transform <Portal mount={target}>...</Portal>
-    fn transform_portal_component(
-        &self,
-        _element: &JSXElement,
-    ) -> Result<Expression, ComponentError> {
-        // TODO: Transform Portal component
-        Err(ComponentError::NotImplemented("Portal component"))
-    }
+/// Replaces `this` in a component's props and children with a `_self$` alias
+///
+/// Functions and classes bind their own `this` and are left alone; arrow
+/// functions are descended into.
+struct ThisReplacer<'a, 'b> {
+    jsx: &'b mut JSXTransformer<'a>,
+    alias: Option<String>,
+}
 
-    /// Transform <Dynamic component={comp} {...props} />
-    fn transform_dynamic_component(
-        &self,
-        _element: &JSXElement,
-    ) -> Result<Expression, ComponentError> {
-        // TODO: Transform Dynamic component
-        Err(ComponentError::NotImplemented("Dynamic component"))
+impl<'a> ThisReplacer<'a, '_> {
+    fn alias(&mut self) -> String {
+        if self.alias.is_none() {
+            self.alias = Some(self.jsx.generate_uid("self$"));
+        }
+        self.alias.clone().unwrap_or_default()
     }
+}
 
-    /// Extract component name from JSX element
-    fn get_component_name(&self, element: &JSXElement) -> Result<String, ComponentError> {
-        match &element.opening_element.name {
-            JSXElementName::Identifier(ident) => Ok(ident.name.to_string()),
-            JSXElementName::IdentifierReference(ident) => Ok(ident.name.to_string()),
-            JSXElementName::NamespacedName(_) => Err(ComponentError::UnsupportedComponent(
-                "Namespaced components not supported".to_string(),
-            )),
-            JSXElementName::MemberExpression(_) => Err(ComponentError::UnsupportedComponent(
-                "Member expression components not yet supported".to_string(),
-            )),
-            JSXElementName::ThisExpression(_) => Err(ComponentError::UnsupportedComponent(
-                "This expression components not yet supported".to_string(),
-            )),
+impl<'a> VisitMut<'a> for ThisReplacer<'a, '_> {
+    fn visit_expression(&mut self, it: &mut Expression<'a>) {
+        if matches!(it, Expression::ThisExpression(_)) {
+            let alias = self.alias();
+            *it = self.jsx.create_identifier(&alias);
+        } else {
+            walk_mut::walk_expression(self, it);
         }
     }
 
-    /// Transform custom (user-defined) components
-    pub fn transform_custom_component(
-        &self,
-        _element: &JSXElement,
-    ) -> Result<Expression, ComponentError> {
-        // TODO: Transform custom components
-        // <MyComponent prop={value}>{children}</MyComponent>
-        // -> createComponent(MyComponent, { prop: value, children: ... })
-        Err(ComponentError::NotImplemented("Custom components"))
+    fn visit_jsx_member_expression_object(&mut self, it: &mut JSXMemberExpressionObject<'a>) {
+        if matches!(it, JSXMemberExpressionObject::ThisExpression(_)) {
+            let alias = self.alias();
+            let ast = self.jsx.ast_builder;
+            *it = JSXMemberExpressionObject::IdentifierReference(
+                ast.alloc_identifier_reference(Span::default(), ast.atom(&alias)),
+            );
+        } else {
+            walk_mut::walk_jsx_member_expression_object(self, it);
+        }
     }
+
+    fn visit_function(&mut self, _it: &mut Function<'a>, _flags: ScopeFlags) {}
+
+    fn visit_class(&mut self, _it: &mut Class<'a>) {}
 }
 
 impl Default for ComponentTransformer {
@@ -154,10 +332,15 @@ impl Default for ComponentTransformer {
 
 #[derive(Debug)]
 pub enum ComponentError {
-    NotImplemented(&'static str),
     UnsupportedComponent(String),
-    InvalidProps(String),
-    MissingRequiredProp(String),
+}
+
+impl std::fmt::Display for ComponentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedComponent(reason) => write!(f, "unsupported component: {reason}"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -4,7 +4,48 @@
 /// - onClick={handler} -> delegated events
 /// - onMount, onCleanup lifecycle events
 /// - Custom event transformations
-use oxc_ast::ast::{Expression, JSXAttribute};
+use oxc_ast::{
+    ast::{
+        Argument, ArrayExpressionElement, Expression, JSXAttribute, JSXAttributeName,
+        JSXAttributeValue, ObjectPropertyKind, PropertyKind, Span, Statement,
+    },
+    AstKind,
+};
+
+use oxc_semantic::Semantic;
+use oxc_syntax::{reference::ReferenceId, symbol::SymbolId};
+use std::collections::HashSet;
+
+use crate::transformer::jsx::JSXTransformer;
+
+/// Events dom-expressions delegates to the document by default
+///
+/// Matches the runtime's `DelegatedEvents` set. Events that do not bubble, like
+/// `focus` and `blur`, and `change` are deliberately left out.
+pub const DELEGATED_EVENTS: &[&str] = &[
+    "beforeinput",
+    "click",
+    "dblclick",
+    "contextmenu",
+    "focusin",
+    "focusout",
+    "input",
+    "keydown",
+    "keyup",
+    "mousedown",
+    "mousemove",
+    "mouseout",
+    "mouseover",
+    "mouseup",
+    "pointerdown",
+    "pointermove",
+    "pointerout",
+    "pointerover",
+    "pointerup",
+    "touchend",
+    "touchmove",
+    "touchstart",
+];
 
 pub struct EventTransformer;
 
@@ -18,59 +59,227 @@ impl EventTransformer {
         attr_name.starts_with("on") && attr_name.len() > 2
     }
 
-    /// Check if an event should be delegated
-    pub fn is_delegated_event(event_name: &str) -> bool {
-        matches!(
-            event_name.to_lowercase().as_str(),
-            "click" | "input" | "change" | "submit" | "focus" | "blur" | "keydown" | "keyup"
-        )
+    /// Check if an attribute is a namespaced listener (`on:click`, `oncapture:click`)
+    pub fn is_listener_attribute(attr_name: &str) -> bool {
+        attr_name.starts_with("on:") || attr_name.starts_with("oncapture:")
+    }
+
+    /// Check if an event should be delegated, given the configured delegated events
+    pub fn is_delegated_event(event_name: &str, delegated_events: &[String]) -> bool {
+        delegated_events.iter().any(|event| event == event_name)
+    }
+
+    /// Collect the references to bindings that always hold the same function
+    ///
+    /// Function declarations and variables initialized with a function (or with
+    /// another such binding) qualify as long as they are never reassigned, like
+    /// Babel's `detectResolvableEventHandler`.
+    pub fn collect_hoisted_handlers(semantic: &Semantic) -> HashSet<ReferenceId> {
+        let scoping = semantic.scoping();
+        scoping
+            .symbol_ids()
+            .filter(|&symbol_id| Self::is_hoisted_handler_symbol(semantic, symbol_id, 0))
+            .flat_map(|symbol_id| {
+                scoping
+                    .get_resolved_reference_ids(symbol_id)
+                    .iter()
+                    .copied()
+            })
+            .collect()
+    }
+
+    /// Whether `symbol_id` is a stable binding to a function
+    fn is_hoisted_handler_symbol(semantic: &Semantic, symbol_id: SymbolId, depth: usize) -> bool {
+        // Bail out of alias chains like `const a = b, b = a;`
+        const MAX_ALIAS_DEPTH: usize = 8;
+        let scoping = semantic.scoping();
+        if depth > MAX_ALIAS_DEPTH || scoping.symbol_is_mutated(symbol_id) {
+            return false;
+        }
+        match semantic.nodes().kind(scoping.symbol_declaration(symbol_id)) {
+            AstKind::Function(_) => true,
+            AstKind::VariableDeclarator(declarator) => match &declarator.init {
+                Some(
+                    Expression::FunctionExpression(_) | Expression::ArrowFunctionExpression(_),
+                ) => true,
+                Some(Expression::Identifier(ident)) => ident
+                    .reference_id
+                    .get()
+                    .and_then(|reference_id| scoping.get_reference(reference_id).symbol_id())
+                    .is_some_and(|symbol_id| {
+                        Self::is_hoisted_handler_symbol(semantic, symbol_id, depth + 1)
+                    }),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether a handler can be passed by reference to `addEventListener`
+    ///
+    /// Inline functions and hoisted handlers cannot change later; anything else
+    /// is looked up again each time the event fires.
+    pub fn is_resolvable_handler(jsx: &JSXTransformer, handler: &Expression) -> bool {
+        match handler {
+            Expression::FunctionExpression(_) | Expression::ArrowFunctionExpression(_) => true,
+            Expression::Identifier(ident) => ident
+                .reference_id
+                .get()
+                .is_some_and(|reference_id| jsx.hoisted_handlers.contains(&reference_id)),
+            _ => false,
+        }
+    }
+
+    /// Wrap handlers that may change as `e => handler(e)`
+    fn wrap_unresolved_handler<'a>(
+        &self,
+        jsx: &JSXTransformer<'a>,
+        handler: Expression<'a>,
+    ) -> Expression<'a> {
+        let wrap = matches!(
+            handler,
+            Expression::Identifier(_) | Expression::StaticMemberExpression(_)
+        ) && !Self::is_resolvable_handler(jsx, &handler);
+        if !wrap {
+            return handler;
+        }
+        let call = jsx.ast_builder.expression_call(
+            Span::default(),
+            handler,
+            None::<oxc_ast::ast::TSTypeParameterInstantiation>,
+            jsx.ast_builder
+                .vec1(Argument::from(jsx.create_identifier("e"))),
+            false,
+        );
+        jsx.create_arrow(&["e"], call)
     }
 
     /// Transform event handler attributes
-    pub fn transform_event_handler(
+    ///
+    /// The handler expression is moved out of `attribute`.
+    pub fn transform_event_handler<'a>(
         &self,
-        attribute: &JSXAttribute,
-    ) -> Result<EventHandling, EventError> {
+        jsx: &mut JSXTransformer<'a>,
+        attribute: &mut JSXAttribute<'a>,
+    ) -> Result<EventHandling<'a>, EventError> {
         let attr_name = self.get_attribute_name(attribute)?;
 
         if !Self::is_event_attribute(&attr_name) {
             return Err(EventError::NotAnEvent(attr_name));
-        }        let event_name = self.extract_event_name(&attr_name)?;
-        let _handler_expr = self.extract_handler_expression(attribute)?;
-
-        // Convert Expression to String representation for now
-        // TODO: Implement proper expression to string conversion
-        let handler = format!("/* handler expression */");
+        }
+        if let Some((namespace, event_name)) = attr_name.split_once(':') {
+            let handler = self.extract_handler_expression(jsx, attribute)?;
+            let (handler, options) = match namespace {
+                "on" => self.split_listener_options(handler),
+                "oncapture" => (
+                    handler,
+                    Some(
+                        jsx.ast_builder
+                            .expression_boolean_literal(Span::default(), true),
+                    ),
+                ),
+                _ => return Err(EventError::InvalidEventName(attr_name)),
+            };
+            // Listener names are used exactly as written, so custom events keep their case
+            return Ok(EventHandling::Listener {
+                event_name: event_name.to_string(),
+                handler,
+                options,
+            });
+        }
+        let event_name = self.extract_event_name(&attr_name)?;
+        let (handler, data) =
+            self.split_bound_handler(self.extract_handler_expression(jsx, attribute)?)?;
 
-        if Self::is_delegated_event(&event_name) {
+        if jsx.options.delegation
+            && Self::is_delegated_event(&event_name, &jsx.options.delegated_events)
+        {
             Ok(EventHandling::Delegated {
                 event_name,
                 handler,
+                data,
             })
         } else {
             Ok(EventHandling::Direct {
                 event_name,
                 handler,
+                data,
             })
         }
     }
 
-    /// Transform lifecycle events (onMount, onCleanup)
-    pub fn transform_lifecycle_event(
+    /// Split the `[handler, data]` form into the handler and its bound data
+    fn split_bound_handler<'a>(
+        &self,
+        handler: Expression<'a>,
+    ) -> Result<(Expression<'a>, Option<Expression<'a>>), EventError> {
+        let Expression::ArrayExpression(array) = handler else {
+            return Ok((handler, None));
+        };
+        let mut elements = array.unbox().elements.into_iter();
+        let handler = elements
+            .next()
+            .filter(|element| element.is_expression())
+            .map(ArrayExpressionElement::into_expression)
+            .ok_or_else(|| EventError::InvalidHandler("missing bound handler".to_string()))?;
+        let data = elements
+            .next()
+            .filter(|element| element.is_expression())
+            .map(ArrayExpressionElement::into_expression);
+        Ok((handler, data))
+    }
+
+    /// Split `{ handleEvent, ...options }` into the handler and its listener options
+    ///
+    /// Only arrow functions and references are split out: a `handleEvent`
+    /// method or `function` may rely on `this` being the listener object, so
+    /// such objects are passed whole as the listener.
+    fn split_listener_options<'a>(
         &self,
-        _attribute: &JSXAttribute,
-    ) -> Result<LifecycleEvent, EventError> {
-        // TODO: Handle dom-expressions lifecycle events
-        // onMount={callback} -> createEffect(callback)
-        // onCleanup={callback} -> onCleanup(callback)
-        Err(EventError::NotImplemented("Lifecycle events"))
+        handler: Expression<'a>,
+    ) -> (Expression<'a>, Option<Expression<'a>>) {
+        let Expression::ObjectExpression(mut object) = handler else {
+            return (handler, None);
+        };
+        let handle_event = object.properties.iter().position(|property| {
+            matches!(
+                property,
+                ObjectPropertyKind::ObjectProperty(property)
+                    if property.kind == PropertyKind::Init
+                        && !property.method
+                        && !property.computed
+                        && property.key.is_specific_static_name("handleEvent")
+                        && matches!(
+                            property.value,
+                            Expression::ArrowFunctionExpression(_) | Expression::Identifier(_)
+                        )
+            )
+        });
+        let splittable = object
+            .properties
+            .iter()
+            .all(|property| matches!(property, ObjectPropertyKind::ObjectProperty(_)));
+        let (Some(index), true) = (handle_event, splittable) else {
+            return (Expression::ObjectExpression(object), None);
+        };
+
+        let ObjectPropertyKind::ObjectProperty(property) = object.properties.remove(index) else {
+            unreachable!("handleEvent is a plain property")
+        };
+        let handler = property.unbox().value;
+        let options =
+            (!object.properties.is_empty()).then_some(Expression::ObjectExpression(object));
+        (handler, options)
     }
 
-    /// Extract attribute name from JSX attribute
-    fn get_attribute_name(&self, _attribute: &JSXAttribute) -> Result<String, EventError> {
-        // TODO: Extract attribute name from JSXAttribute
-        // Handle both regular and namespaced attribute names
-        Ok("onClick".to_string()) // Placeholder
+    /// Extract attribute name from JSX attribute, keeping any namespace (`on:click`)
+    fn get_attribute_name(&self, attribute: &JSXAttribute) -> Result<String, EventError> {
+        Ok(match &attribute.name {
+            JSXAttributeName::Identifier(ident) => ident.name.to_string(),
+            JSXAttributeName::NamespacedName(name) => {
+                format!("{}:{}", name.namespace.name, name.name.name)
+            }
+        })
     }
 
     /// Extract event name from attribute name (onClick -> click)
@@ -84,33 +293,154 @@ impl EventTransformer {
     }
 
     /// Extract handler expression from JSX attribute
-    fn extract_handler_expression(
+    fn extract_handler_expression<'a>(
         &self,
-        _attribute: &JSXAttribute,
-    ) -> Result<Expression, EventError> {
-        // TODO: Extract the expression from JSXAttribute value
-        todo!("Extract handler expression")
+        jsx: &JSXTransformer<'a>,
+        attribute: &mut JSXAttribute<'a>,
+    ) -> Result<Expression<'a>, EventError> {
+        match &mut attribute.value {
+            Some(JSXAttributeValue::ExpressionContainer(expr_container)) => jsx
+                .take_expression(expr_container)
+                .ok_or_else(|| EventError::InvalidHandler("empty expression".to_string())),
+            _ => Err(EventError::InvalidHandler(
+                "event handlers must be expressions".to_string(),
+            )),
+        }
     }
 
-    /// Generate delegated event binding code
-    pub fn generate_delegated_binding(
+    /// Generate `_el$.$$click = handler` and record the event for `_$delegateEvents`
+    ///
+    /// Bound data is stored next to the handler as `_el$.$$clickData`.
+    pub fn generate_delegated_binding<'a>(
         &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &str,
         event_name: &str,
-        _handler: &Expression,
-    ) -> Result<String, EventError> {
-        // TODO: Generate code for delegated event handling
-        // This will be used in template generation
-        Ok(format!("/* delegated {} */", event_name))
+        handler: Expression<'a>,
+        data: Option<Expression<'a>>,
+    ) -> Result<Vec<Statement<'a>>, EventError> {
+        jsx.delegated_events.insert(event_name.to_string());
+        let mut bindings =
+            vec![jsx.create_assignment(&format!("{element}.$${event_name}"), handler)];
+        if let Some(data) = data {
+            bindings.push(jsx.create_assignment(&format!("{element}.$${event_name}Data"), data));
+        }
+        Ok(bindings
+            .into_iter()
+            .map(|binding| {
+                jsx.ast_builder
+                    .statement_expression(Span::default(), binding)
+            })
+            .collect())
     }
 
-    /// Generate direct event binding code  
-    pub fn generate_direct_binding(
+    /// Generate `_el$.addEventListener("name", handler)`
+    ///
+    /// Handlers that are not resolvable are wrapped as `e => handler(e)`.
+    /// With bound data the runtime helper does the binding:
+    /// `_$addEventListener(_el$, "name", [handler, data])`.
+    pub fn generate_direct_binding<'a>(
         &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &str,
         event_name: &str,
-        _handler: &Expression,
-    ) -> Result<String, EventError> {
-        // TODO: Generate code for direct event handling
-        Ok(format!("addEventListener('{}', {})", event_name, "handler"))
+        handler: Expression<'a>,
+        data: Option<Expression<'a>>,
+    ) -> Result<Vec<Statement<'a>>, EventError> {
+        if let Some(data) = data {
+            let event_name = jsx.ast_builder.expression_string_literal(
+                Span::default(),
+                jsx.ast_builder.atom(event_name),
+                None,
+            );
+            let handler = jsx.ast_builder.expression_array(
+                Span::default(),
+                jsx.ast_builder.vec_from_iter([
+                    ArrayExpressionElement::from(handler),
+                    ArrayExpressionElement::from(data),
+                ]),
+            );
+            jsx.register_import("addEventListener");
+            let call = jsx.create_call(
+                "_$addEventListener",
+                vec![jsx.create_identifier(element), event_name, handler],
+            );
+            return Ok(vec![jsx
+                .ast_builder
+                .statement_expression(Span::default(), call)]);
+        }
+
+        let handler = self.wrap_unresolved_handler(jsx, handler);
+        Ok(vec![self.create_add_event_listener(
+            jsx, element, event_name, handler, None,
+        )])
+    }
+
+    /// Generate `_el$.addEventListener("name", handler[, options])` for `on:`/`oncapture:`
+    pub fn generate_listener_binding<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &str,
+        event_name: &str,
+        handler: Expression<'a>,
+        options: Option<Expression<'a>>,
+    ) -> Result<Vec<Statement<'a>>, EventError> {
+        let handler = self.wrap_unresolved_handler(jsx, handler);
+        Ok(vec![self.create_add_event_listener(
+            jsx, element, event_name, handler, options,
+        )])
+    }
+
+    /// Create the statement `_el$.addEventListener("name", handler[, options])`
+    fn create_add_event_listener<'a>(
+        &self,
+        jsx: &JSXTransformer<'a>,
+        element: &str,
+        event_name: &str,
+        handler: Expression<'a>,
+        options: Option<Expression<'a>>,
+    ) -> Statement<'a> {
+        let callee = jsx.create_member_path(&format!("{element}.addEventListener"));
+        let event_name = jsx.ast_builder.expression_string_literal(
+            Span::default(),
+            jsx.ast_builder.atom(event_name),
+            None,
+        );
+        let mut arguments = jsx
+            .ast_builder
+            .vec_from_iter([Argument::from(event_name), Argument::from(handler)]);
+        arguments.extend(options.map(Argument::from));
+        let call = jsx.ast_builder.expression_call(
+            Span::default(),
+            callee,
+            None::<oxc_ast::ast::TSTypeParameterInstantiation>,
+            arguments,
+            false,
+        );
+        jsx.ast_builder.statement_expression(Span::default(), call)
+    }
+
+    /// Generate `_$delegateEvents(["click", ...])` for the events delegated in the module
+    pub fn generate_delegate_events<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+    ) -> Option<Statement<'a>> {
+        if jsx.delegated_events.is_empty() {
+            return None;
+        }
+        let events = jsx
+            .ast_builder
+            .vec_from_iter(jsx.delegated_events.iter().map(|event| {
+                ArrayExpressionElement::from(jsx.ast_builder.expression_string_literal(
+                    Span::default(),
+                    jsx.ast_builder.atom(event),
+                    None,
+                ))
+            }));
+        let events = jsx.ast_builder.expression_array(Span::default(), events);
+        jsx.register_import("delegateEvents");
+        let call = jsx.create_call("_$delegateEvents", vec![events]);
+        Some(jsx.ast_builder.statement_expression(Span::default(), call))
     }
 }
 
@@ -121,32 +451,44 @@ impl Default for EventTransformer {
 }
 
 #[derive(Debug)]
-pub enum EventHandling {
+pub enum EventHandling<'a> {
     /// Events that use dom-expressions's delegation system
     Delegated {
         event_name: String,
-        handler: String, // Use String instead of Expression for now
+        handler: Expression<'a>,
+        /// Data bound with `[handler, data]`, passed to the handler first
+        data: Option<Expression<'a>>,
     },
     /// Events that are bound directly to elements
     Direct {
         event_name: String,
-        handler: String, // Use String instead of Expression for now
+        handler: Expression<'a>,
+        data: Option<Expression<'a>>,
+    },
+    /// `on:` and `oncapture:` listeners, added with `addEventListener` as written
+    Listener {
+        event_name: String,
+        handler: Expression<'a>,
+        /// `true` for capture, or the options split out of `{ handleEvent, ... }`
+        options: Option<Expression<'a>>,
     },
-}
-
-#[derive(Debug)]
-pub enum LifecycleEvent {
-    Mount(String),   // Use String instead of Expression for now
-    Cleanup(String), // Use String instead of Expression for now
 }
 
 #[derive(Debug)]
 pub enum EventError {
-    NotImplemented(&'static str),
     NotAnEvent(String),
     InvalidEventName(String),
     InvalidHandler(String),
-    UnsupportedEvent(String),
+}
+
+impl std::fmt::Display for EventError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotAnEvent(name) => write!(f, "`{name}` is not an event attribute"),
+            Self::InvalidEventName(name) => write!(f, "invalid event name `{name}`"),
+            Self::InvalidHandler(reason) => write!(f, "invalid event handler: {reason}"),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -165,11 +507,19 @@ mod tests {
 
     #[test]
     fn test_delegated_event_detection() {
-        assert!(EventTransformer::is_delegated_event("click"));
-        assert!(EventTransformer::is_delegated_event("input"));
-        assert!(EventTransformer::is_delegated_event("submit"));
-        assert!(!EventTransformer::is_delegated_event("mount"));
-        assert!(!EventTransformer::is_delegated_event("resize"));
+        let delegated: Vec<String> = DELEGATED_EVENTS.iter().map(|e| e.to_string()).collect();
+        assert!(EventTransformer::is_delegated_event("click", &delegated));
+        assert!(EventTransformer::is_delegated_event("input", &delegated));
+        assert!(EventTransformer::is_delegated_event("dblclick", &delegated));
+        assert!(EventTransformer::is_delegated_event(
+            "touchstart",
+            &delegated
+        ));
+        assert!(!EventTransformer::is_delegated_event("change", &delegated));
+        assert!(!EventTransformer::is_delegated_event("blur", &delegated));
+        assert!(!EventTransformer::is_delegated_event("submit", &delegated));
+        assert!(!EventTransformer::is_delegated_event("mount", &delegated));
+        assert!(!EventTransformer::is_delegated_event("resize", &delegated));
     }
 
     #[test]
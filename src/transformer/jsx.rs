@@ -1,75 +1,488 @@
-use oxc_allocator::Allocator;
-use oxc_ast::ast::{Expression, JSXExpressionContainer};
-use std::collections::HashMap;
+use crate::transformer::components::{ComponentError, ComponentTransformer};
+use crate::transformer::events::{EventError, EventHandling, EventTransformer};
+use crate::transformer::ssr::SSRTransformer;
+use crate::transformer::universal::UniversalTransformer;
+use crate::transformer::{DomExpressionsTransformOptions, GenerateMode};
+use crate::utils::ast_utils::AstUtils;
+use crate::utils::template::TemplateGenerator;
+use indexmap::{IndexMap, IndexSet};
+use oxc_allocator::{Allocator, CloneIn, TakeIn};
+use oxc_ast::ast::{
+    Argument, ArrayExpressionElement, AssignmentOperator, AssignmentTarget, BinaryOperator,
+    Expression, FormalParameterKind, FunctionType, JSXAttribute, JSXAttributeItem,
+    JSXAttributeName, JSXAttributeValue, JSXChild, JSXElement, JSXExpressionContainer, JSXFragment,
+    LogicalExpression, LogicalOperator, MemberExpression, ObjectPropertyKind, PropertyKey,
+    PropertyKind, SimpleAssignmentTarget, Span, Statement, TemplateElementValue, UnaryOperator,
+    VariableDeclarationKind, VariableDeclarator,
+};
+use oxc_ast::AstBuilder;
+use oxc_semantic::Semantic;
+use oxc_syntax::identifier::is_identifier_name;
+use oxc_syntax::reference::ReferenceId;
+use std::collections::{HashMap, HashSet};
+
+/// Elements that never have children or a closing tag
+pub(crate) const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "keygen", "link", "menuitem",
+    "meta", "param", "source", "track", "wbr",
+];
+
+/// Standard HTML elements, owned by the DOM renderer in `GenerateMode::Dynamic`
+pub(crate) const HTML_ELEMENTS: &[&str] = &[
+    "a",
+    "abbr",
+    "address",
+    "area",
+    "article",
+    "aside",
+    "audio",
+    "b",
+    "base",
+    "bdi",
+    "bdo",
+    "blockquote",
+    "body",
+    "br",
+    "button",
+    "canvas",
+    "caption",
+    "cite",
+    "code",
+    "col",
+    "colgroup",
+    "data",
+    "datalist",
+    "dd",
+    "del",
+    "details",
+    "dfn",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "embed",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "i",
+    "iframe",
+    "img",
+    "input",
+    "ins",
+    "kbd",
+    "label",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "map",
+    "mark",
+    "menu",
+    "meta",
+    "meter",
+    "nav",
+    "noscript",
+    "object",
+    "ol",
+    "optgroup",
+    "option",
+    "output",
+    "p",
+    "param",
+    "picture",
+    "pre",
+    "progress",
+    "q",
+    "rp",
+    "rt",
+    "ruby",
+    "s",
+    "samp",
+    "script",
+    "search",
+    "section",
+    "select",
+    "slot",
+    "small",
+    "source",
+    "span",
+    "strong",
+    "style",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "textarea",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "title",
+    "tr",
+    "track",
+    "u",
+    "ul",
+    "var",
+    "video",
+    "wbr",
+];
+
+/// Properties that replace an element's children when assigned
+pub(crate) const CHILD_PROPERTIES: &[&str] = &["innerHTML", "textContent", "innerText", "children"];
+
+/// Attributes whose presence alone means `true`
+pub(crate) const BOOLEAN_ATTRIBUTES: &[&str] = &[
+    "allowfullscreen",
+    "async",
+    "autofocus",
+    "autoplay",
+    "checked",
+    "controls",
+    "default",
+    "disabled",
+    "formnovalidate",
+    "hidden",
+    "indeterminate",
+    "inert",
+    "ismap",
+    "loop",
+    "multiple",
+    "muted",
+    "nomodule",
+    "novalidate",
+    "open",
+    "playsinline",
+    "readonly",
+    "required",
+    "reversed",
+    "seamless",
+    "selected",
+];
+
+/// Elements that live in the SVG namespace
+pub(crate) const SVG_ELEMENTS: &[&str] = &[
+    "altGlyph",
+    "altGlyphDef",
+    "altGlyphItem",
+    "animate",
+    "animateColor",
+    "animateMotion",
+    "animateTransform",
+    "circle",
+    "clipPath",
+    "color-profile",
+    "cursor",
+    "defs",
+    "desc",
+    "ellipse",
+    "feBlend",
+    "feColorMatrix",
+    "feComponentTransfer",
+    "feComposite",
+    "feConvolveMatrix",
+    "feDiffuseLighting",
+    "feDisplacementMap",
+    "feDistantLight",
+    "feDropShadow",
+    "feFlood",
+    "feFuncA",
+    "feFuncB",
+    "feFuncG",
+    "feFuncR",
+    "feGaussianBlur",
+    "feImage",
+    "feMerge",
+    "feMergeNode",
+    "feMorphology",
+    "feOffset",
+    "fePointLight",
+    "feSpecularLighting",
+    "feSpotLight",
+    "feTile",
+    "feTurbulence",
+    "filter",
+    "font",
+    "font-face",
+    "font-face-format",
+    "font-face-name",
+    "font-face-src",
+    "font-face-uri",
+    "foreignObject",
+    "g",
+    "glyph",
+    "glyphRef",
+    "hkern",
+    "image",
+    "line",
+    "linearGradient",
+    "marker",
+    "mask",
+    "metadata",
+    "missing-glyph",
+    "mpath",
+    "path",
+    "pattern",
+    "polygon",
+    "polyline",
+    "radialGradient",
+    "rect",
+    "set",
+    "stop",
+    "svg",
+    "switch",
+    "symbol",
+    "text",
+    "textPath",
+    "tref",
+    "tspan",
+    "use",
+    "view",
+    "vkern",
+];
+
+/// Properties set directly on the element instead of through `setAttribute`
+const PROPERTIES: &[&str] = &["value", "checked", "selected", "muted"];
+
+/// Namespace URIs for prefixed attributes like `xlink:href`, set with `setAttributeNS`
+const SVG_NAMESPACES: &[(&str, &str)] = &[
+    ("xlink", "http://www.w3.org/1999/xlink"),
+    ("xml", "http://www.w3.org/XML/1998/namespace"),
+];
+
+/// Attribute names that differ between JSX and HTML
+const ATTRIBUTE_ALIASES: &[(&str, &str)] = &[("className", "class"), ("htmlFor", "for")];
+
+/// Property names for the previous values object of a batched effect
+const EFFECT_KEYS: &str = "etaoinshrdlucwmfygpbvkxjqz";
 
 /// Information about a dynamic attribute that needs runtime processing
-#[derive(Debug, Clone)]
-pub struct DynamicAttribute {
+#[derive(Debug)]
+pub struct DynamicAttribute<'a> {
     pub name: String,
-    pub expression: String, // We'll store the expression as a string for now
-    pub is_style_object: bool,
-    pub is_class_list: bool,
+    /// Variable holding the element the attribute is applied to
+    pub element: String,
+    /// The user's expression, moved out of the attribute's expression container
+    pub expression: Expression<'a>,
     pub requires_effect: bool,
 }
 
 /// Information about dynamic text insertions
-#[derive(Debug, Clone)]
-pub struct TextInsertion {
-    pub expression: String,
+#[derive(Debug)]
+pub struct TextInsertion<'a> {
+    /// Variable holding the element the expression is inserted into
+    pub parent: String,
+    /// The user's expression, moved out of the child expression container
+    pub expression: Expression<'a>,
     pub position: InsertionPosition,
 }
 
 /// Position information for text insertions
 #[derive(Debug, Clone)]
 pub enum InsertionPosition {
-    BeforeNode(String), // Insert before this node reference
-    AfterNode(String),  // Insert after this node reference
-    AtEnd,              // Insert at the end (null position)
+    Replace,                // Sole content of the parent (no marker)
+    BeforeNode(String),     // Insert before this node reference or `<!>` marker
+    Marker(String, String), // Hydration end marker and the content claimed by `_$getNextMarker`
+    AtEnd,                  // Insert at the end (null position)
 }
 
-/// Template information including both static HTML and dynamic parts
-#[derive(Debug, Clone)]
-pub struct TemplateInfo {
+/// Result of lowering an element or text node into template HTML
+///
+/// Nodes that generated code needs to reach get an `id`; the declarations walk
+/// to them from the template root via `firstChild`/`nextSibling`.
+#[derive(Debug)]
+pub struct TemplateInfo<'a> {
     pub html: String,
-    pub dynamic_attributes: Vec<DynamicAttribute>,
-    pub text_insertions: Vec<TextInsertion>,
-    pub has_dynamic_content: bool,
+    /// Variable referencing this node, when generated code needs one
+    pub id: Option<String>,
+    /// `var` declarators walking from the root to every referenced node
+    pub declarations: Vec<VariableDeclarator<'a>>,
+    /// Statements run once after the template is cloned
+    pub exprs: Vec<Statement<'a>>,
+    /// Attributes re-applied in an effect whenever their inputs change
+    pub dynamic_attributes: Vec<DynamicAttribute<'a>>,
+    pub is_text: bool,
+    /// Events or spreads that must be replayed with `_$runHydrationEvents`
+    pub has_hydratable_event: bool,
+}
+
+impl<'a> TemplateInfo<'a> {
+    fn new(html: String, id: Option<String>) -> Self {
+        Self {
+            html,
+            id,
+            declarations: Vec::new(),
+            exprs: Vec::new(),
+            dynamic_attributes: Vec::new(),
+            is_text: false,
+            has_hydratable_event: false,
+        }
+    }
+
+    fn text(html: String, id: Option<String>) -> Self {
+        Self {
+            is_text: true,
+            ..Self::new(html, id)
+        }
+    }
+
+    pub fn has_dynamic_content(&self) -> bool {
+        !self.declarations.is_empty()
+            || !self.exprs.is_empty()
+            || !self.dynamic_attributes.is_empty()
+    }
+}
+
+/// Helpers every renderer shares, always imported from the main `module_name`
+const SHARED_HELPERS: &[&str] = &["createComponent", "effect", "memo", "mergeProps"];
+
+/// A runtime helper the generated code imports: `import { name as local } from "module"`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeImport {
+    pub name: String,
+    pub module: String,
+    /// `_$name`, numbered when another renderer's helper already took that name
+    pub local: String,
+}
+
+/// A lowered JSX child: either part of the template or an expression to insert
+enum ChildNode<'a> {
+    Template(TemplateInfo<'a>),
+    Insertion(Expression<'a>),
+}
+
+/// Module-level bookkeeping to restore when an element fails to compile,
+/// see `JSXTransformer::checkpoint`
+pub(crate) struct Checkpoint {
+    template_counter: usize,
+    uid_counters: HashMap<String, usize>,
+    templates: usize,
+    ssr_templates: usize,
+    required_imports: usize,
+    delegated_events: usize,
+    this_aliases: usize,
 }
 
 pub struct JSXTransformer<'a> {
+    pub(crate) options: &'a DomExpressionsTransformOptions,
     template_counter: usize,
-    #[allow(dead_code)]
-    allocator: &'a Allocator,
-    pub templates: HashMap<String, String>,
-    /// Enhanced template information for dynamic processing
-    pub template_info: HashMap<String, TemplateInfo>,
-    /// Required runtime imports for current transformation
-    pub required_imports: std::collections::HashSet<String>,
+    pub(crate) allocator: &'a Allocator,
+    pub(crate) ast_builder: AstBuilder<'a>,
+    /// Counters behind `generate_uid`, keyed by base name
+    uid_counters: HashMap<String, usize>,
+    /// Template HTML keyed by template name, in creation order
+    pub templates: IndexMap<String, String>,
+    /// Templates wrapped in `<svg>` so their SVG root is parsed in the SVG namespace
+    pub svg_templates: HashSet<String>,
+    /// Server template parts keyed by template name, see `SSRTransformer`
+    pub ssr_templates: IndexMap<String, Vec<String>>,
+    /// Required runtime imports for current transformation, in registration order
+    pub required_imports: Vec<RuntimeImport>,
+    /// Module of the renderer compiling the current element
+    renderer_module: String,
+    /// Whether the current element is built with universal calls rather than a template
+    pub(crate) universal: bool,
+    /// Events bound through delegation, for the module's `_$delegateEvents` call
+    pub delegated_events: IndexSet<String>,
+    /// `_self$` aliases for `this` to declare ahead of the statement being compiled
+    pub(crate) this_aliases: Vec<String>,
+    /// References to stable function bindings, see `EventTransformer::collect_hoisted_handlers`
+    pub(crate) hoisted_handlers: HashSet<ReferenceId>,
+    /// References to `const` and imported bindings, see `collect_constant_references`
+    pub(crate) constant_references: HashSet<ReferenceId>,
 }
 
 impl<'a> JSXTransformer<'a> {
-    pub fn new(allocator: &'a Allocator) -> Self {
+    pub fn new(options: &'a DomExpressionsTransformOptions, allocator: &'a Allocator) -> Self {
         Self {
+            options,
             template_counter: 0,
             allocator,
-            templates: HashMap::new(),
-            template_info: HashMap::new(),
-            required_imports: std::collections::HashSet::new(),
+            ast_builder: AstBuilder::new(allocator),
+            uid_counters: HashMap::new(),
+            templates: IndexMap::new(),
+            svg_templates: HashSet::new(),
+            ssr_templates: IndexMap::new(),
+            required_imports: Vec::new(),
+            renderer_module: options.module_name.clone(),
+            universal: options.generate == GenerateMode::Universal,
+            delegated_events: IndexSet::new(),
+            this_aliases: Vec::new(),
+            hoisted_handlers: HashSet::new(),
+            constant_references: HashSet::new(),
         }
     }
 
-    pub fn get_templates(&self) -> &HashMap<String, String> {
-        &self.templates
+    /// Collect references to bindings that can never be assigned, `const`
+    /// declarations and imports
+    ///
+    /// A `ref` naming one of these can only be a callback, see `create_ref`.
+    pub fn collect_constant_references(semantic: &Semantic) -> HashSet<ReferenceId> {
+        let scoping = semantic.scoping();
+        scoping
+            .symbol_ids()
+            .filter(|&symbol_id| {
+                let flags = scoping.symbol_flags(symbol_id);
+                flags.is_const_variable() || flags.is_import()
+            })
+            .flat_map(|symbol_id| {
+                scoping
+                    .get_resolved_reference_ids(symbol_id)
+                    .iter()
+                    .copied()
+            })
+            .collect()
     }
 
-    pub fn create_template_declaration(&self, template_name: &str, template_html: &str) -> String {
-        format!(
-            "var {} = /*#__PURE__*/ _$template(`{}`);",
-            template_name, template_html
-        )
+    /// Record the templates, imports and names created so far
+    pub(crate) fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            template_counter: self.template_counter,
+            uid_counters: self.uid_counters.clone(),
+            templates: self.templates.len(),
+            ssr_templates: self.ssr_templates.len(),
+            required_imports: self.required_imports.len(),
+            delegated_events: self.delegated_events.len(),
+            this_aliases: self.this_aliases.len(),
+        }
     }
 
-    fn get_next_template_name(&mut self) -> String {
+    /// Forget everything created since `checkpoint`, so a failed element
+    /// leaves no templates, imports or events behind
+    pub(crate) fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.template_counter = checkpoint.template_counter;
+        self.uid_counters = checkpoint.uid_counters;
+        self.templates.truncate(checkpoint.templates);
+        self.ssr_templates.truncate(checkpoint.ssr_templates);
+        let templates = &self.templates;
+        self.svg_templates
+            .retain(|name| templates.contains_key(name));
+        self.required_imports.truncate(checkpoint.required_imports);
+        self.delegated_events.truncate(checkpoint.delegated_events);
+        self.this_aliases.truncate(checkpoint.this_aliases);
+    }
+
+    pub fn get_templates(&self) -> &IndexMap<String, String> {
+        &self.templates
+    }
+
+    pub(crate) fn get_next_template_name(&mut self) -> String {
         self.template_counter += 1;
         if self.template_counter == 1 {
             "_tmpl$".to_string()
@@ -78,557 +491,1857 @@ impl<'a> JSXTransformer<'a> {
         }
     }
 
+    /// Generate a module-unique identifier like Babel's `generateUidIdentifier`
+    ///
+    /// `el$` yields `_el$`, `_el$2`, `_el$3`, ...
+    pub(crate) fn generate_uid(&mut self, name: &str) -> String {
+        let counter = self.uid_counters.entry(name.to_string()).or_insert(0);
+        *counter += 1;
+        if *counter == 1 {
+            format!("_{}", name)
+        } else {
+            format!("_{}{}", name, counter)
+        }
+    }
+
     /// Transform a JSX element into a dom-expressions template call
+    ///
+    /// Expressions found in attributes and children are moved out of `element`
+    /// into the generated code, so the element is left hollow afterwards.
     pub fn transform_jsx_element(
         &mut self,
-        element: &oxc_ast::ast::JSXElement,
-    ) -> Result<String, TransformError> {
-        // Extract static template structure and collect dynamic attributes and text insertions
-        let (template_html, dynamic_attrs, text_insertions) =
-            self.extract_template_with_dynamics_and_text(element);
+        element: &mut JSXElement<'a>,
+    ) -> Result<Expression<'a>, TransformError> {
+        let tag_name = AstUtils::get_element_name(element).unwrap_or_default();
+        if AstUtils::is_component(&tag_name) {
+            return ComponentTransformer::new().transform_custom_component(self, element);
+        }
+        if self.options.generate_ssr {
+            return SSRTransformer::new().transform_element(self, element);
+        }
+        if self.options.generate == GenerateMode::Dynamic {
+            return self.transform_with_renderer(element, &tag_name);
+        }
+        if self.options.generate == GenerateMode::Universal {
+            return UniversalTransformer::new().transform_element(self, element);
+        }
+
+        self.transform_template_element(element)
+    }
 
-        // Generate unique template name
+    /// Clone a native element from an HTML template
+    ///
+    /// An SVG element outside of `<svg>`, like a lone `<rect/>`, is wrapped in
+    /// `<svg>` and flagged so the runtime parses it as SVG and unwraps it.
+    fn transform_template_element(
+        &mut self,
+        element: &mut JSXElement<'a>,
+    ) -> Result<Expression<'a>, TransformError> {
+        let tag_name = AstUtils::get_element_name(element).unwrap_or_default();
+        // Reserve the template's slot first so it is declared ahead of the
+        // templates of components nested in its children
         let template_name = self.get_next_template_name();
+        self.templates.insert(template_name.clone(), String::new());
+        let template_info = self.transform_element(element, false)?;
 
-        // Store template for later code generation
-        self.templates
-            .insert(template_name.clone(), template_html.clone());
+        let mut html = template_info.html.clone();
+        if tag_name != "svg" && SVG_ELEMENTS.contains(&tag_name.as_str()) {
+            html = format!("<svg>{}</svg>", html);
+            self.svg_templates.insert(template_name.clone());
+        }
+        self.templates.insert(template_name.clone(), html);
 
-        // Store enhanced template info
-        let template_info = TemplateInfo {
-            html: template_html,
-            dynamic_attributes: dynamic_attrs.clone(),
-            text_insertions: text_insertions.clone(),
-            has_dynamic_content: !dynamic_attrs.is_empty() || !text_insertions.is_empty(),
-        };
-        self.template_info
-            .insert(template_name.clone(), template_info);
+        Ok(self.create_template_wrapper(&template_name, template_info))
+    }
 
-        // Generate appropriate call (static or dynamic)
-        if dynamic_attrs.is_empty() && text_insertions.is_empty() {
-            Ok(format!("{}()", template_name))
+    /// Compile a native element with the renderer that owns its tag
+    ///
+    /// The renderer stays active for the element's subtree, so its helpers are
+    /// imported from the renderer's module.
+    fn transform_with_renderer(
+        &mut self,
+        element: &mut JSXElement<'a>,
+        tag_name: &str,
+    ) -> Result<Expression<'a>, TransformError> {
+        let (module, universal) = self.renderer_for(tag_name);
+        let previous_module = std::mem::replace(&mut self.renderer_module, module);
+        let previous_universal = std::mem::replace(&mut self.universal, universal);
+        let expression = if universal {
+            UniversalTransformer::new().transform_element(self, element)
         } else {
-            Ok(self.generate_dynamic_wrapper_with_text(
-                &template_name,
-                &dynamic_attrs,
-                &text_insertions,
-            ))
-        }
+            self.transform_template_element(element)
+        };
+        self.renderer_module = previous_module;
+        self.universal = previous_universal;
+        expression
     }
 
-    /// Extract template HTML from a JSX element (legacy method)
-    fn extract_template(&self, element: &oxc_ast::ast::JSXElement) -> String {
-        let (html, _) = self.extract_template_with_dynamics(element);
-        html
+    /// The module and codegen style for a native tag in `GenerateMode::Dynamic`
+    ///
+    /// Tags no renderer claims go to the universal renderer at `module_name`.
+    fn renderer_for(&self, tag_name: &str) -> (String, bool) {
+        self.options
+            .renderers
+            .iter()
+            .find(|renderer| renderer.elements.iter().any(|element| element == tag_name))
+            .map_or_else(
+                || (self.options.module_name.clone(), true),
+                |renderer| (renderer.module_name.clone(), !renderer.is_dom()),
+            )
     }
 
-    /// Extract template HTML and collect dynamic attributes from a JSX element
-    fn extract_template_with_dynamics(
-        &self,
-        element: &oxc_ast::ast::JSXElement,
-    ) -> (String, Vec<DynamicAttribute>) {
-        use oxc_ast::ast::{
-            JSXAttributeItem, JSXAttributeName, JSXAttributeValue, JSXChild, JSXElementName,
-        };
+    /// Whether a native child belongs to a different renderer than its parent
+    ///
+    /// Such children are compiled on their own and inserted like expressions.
+    pub(crate) fn is_foreign_element(&self, tag_name: &str) -> bool {
+        self.options.generate == GenerateMode::Dynamic
+            && self.renderer_for(tag_name) != (self.renderer_module.clone(), self.universal)
+    }
 
-        let mut dynamic_attributes = Vec::new();
+    /// Lower a native element and its subtree into template HTML
+    ///
+    /// Every element gets an id unless `skip_id` says nothing in or after it
+    /// needs to be referenced.
+    fn transform_element(
+        &mut self,
+        element: &mut JSXElement<'a>,
+        skip_id: bool,
+    ) -> Result<TemplateInfo<'a>, TransformError> {
+        let tag_name = AstUtils::get_element_name(element).unwrap_or_default();
+        let id = (!skip_id).then(|| self.generate_uid("el$"));
+        let mut info = TemplateInfo::new(format!("<{}", tag_name), id);
+
+        self.transform_attributes(element, &mut info)?;
+        info.html.push('>');
+
+        if !VOID_ELEMENTS.contains(&tag_name.as_str()) {
+            // `<noscript>` content never hydrates, so it stays out of the template
+            if !(self.options.hydratable && tag_name == "noscript") {
+                self.transform_children(&mut element.children, &mut info)?;
+            }
+            info.html.push_str("</");
+            info.html.push_str(&tag_name);
+            info.html.push('>');
+        }
 
-        match &element.opening_element.name {
-            JSXElementName::Identifier(ident) => {
-                let tag_name = &ident.name;
-                let mut html = String::new();
+        Ok(info)
+    }
 
-                // Start opening tag
-                html.push('<');
-                html.push_str(tag_name);
+    /// Write static attributes into the template and collect the dynamic ones
+    ///
+    /// With spreads present, the spreads and every attribute after the first one
+    /// (plus earlier dynamic ones) are applied together through `_$spread`, so
+    /// later props still override earlier ones.
+    fn transform_attributes(
+        &mut self,
+        element: &mut JSXElement<'a>,
+        info: &mut TemplateInfo<'a>,
+    ) -> Result<(), TransformError> {
+        self.merge_class_attributes(&mut element.opening_element.attributes);
+        let attributes = &mut element.opening_element.attributes;
+        let mut spread_handled = vec![false; attributes.len()];
+        let mut spread = None;
+        if attributes
+            .iter()
+            .any(|attr| matches!(attr, JSXAttributeItem::SpreadAttribute(_)))
+        {
+            let tag_name = AstUtils::get_element_name(element).unwrap_or_default();
+            let has_children = !element.children.is_empty();
+            spread = self.transform_spread_attributes(
+                &mut element.opening_element.attributes,
+                &mut spread_handled,
+                info.id.as_deref(),
+                SVG_ELEMENTS.contains(&tag_name.as_str()),
+                has_children,
+            );
+        }
 
-                // Add attributes
-                for attr in &element.opening_element.attributes {
-                    match attr {
-                        JSXAttributeItem::Attribute(attr) => {
-                            if let JSXAttributeName::Identifier(name_ident) = &attr.name {
-                                let attr_name = &name_ident.name;
-
-                                // Convert 'for' to 'for' (keep as is in template)
-                                let attr_name = if attr_name == "for" { "for" } else { attr_name };
-
-                                match &attr.value {
-                                    Some(JSXAttributeValue::StringLiteral(lit)) => {
-                                        html.push(' ');
-                                        html.push_str(attr_name);
-                                        html.push('=');
-                                        // Check if quotes are needed (dom-expressions style)
-                                        let value = &lit.value;
-                                        if self.needs_quotes(value) {
-                                            html.push('"');
-                                            html.push_str(value);
-                                            html.push('"');
-                                        } else {
-                                            html.push_str(value);
-                                        }
-                                    }
-                                    Some(JSXAttributeValue::ExpressionContainer(
-                                        expr_container,
-                                    )) => {
-                                        // Extract expression as string - this is a simplified approach
-                                        let expression_str =
-                                            self.extract_expression_string(expr_container);
-
-                                        // Create dynamic attribute info
-                                        let is_style_object =
-                                            attr_name == "style" && expression_str.starts_with('{');
-                                        let is_class_list = attr_name == "classList";
-                                        let requires_effect =
-                                            self.expression_requires_effect(&expression_str);
-
-                                        let dynamic_attr = DynamicAttribute {
-                                            name: attr_name.to_string(),
-                                            expression: expression_str,
-                                            is_style_object,
-                                            is_class_list,
-                                            requires_effect,
-                                        };
-
-                                        dynamic_attributes.push(dynamic_attr);
-
-                                        // Don't add to template HTML - will be handled dynamically
-                                    }
-                                    None => {
-                                        // Boolean attribute
-                                        html.push(' ');
-                                        html.push_str(attr_name);
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-                        _ => {} // Handle other attribute types if needed
-                    }
+        let attributes = element.opening_element.attributes.iter_mut();
+        for (attr, handled) in attributes.zip(spread_handled) {
+            if let JSXAttributeItem::Attribute(attr) = attr {
+                if !handled {
+                    self.transform_attribute(attr, info)?;
                 }
+            }
+        }
+        if spread.is_some() {
+            info.has_hydratable_event = true;
+        }
+        info.exprs.extend(spread);
+        Ok(())
+    }
 
-                // Check if self-closing
-                if element.closing_element.is_none() && element.children.is_empty() {
-                    html.push('>');
-                    return (html, dynamic_attributes);
+    /// Fold every `class` and `className` into the first one, so the template
+    /// gets a single `class`
+    ///
+    /// Static values are joined with spaces; with any expression the result is
+    /// a template literal like `` `a ${b || ""}` ``.
+    fn merge_class_attributes(
+        &mut self,
+        attributes: &mut oxc_allocator::Vec<'a, JSXAttributeItem<'a>>,
+    ) {
+        let is_class = |attr: &JSXAttributeItem<'a>| {
+            matches!(
+                attr,
+                JSXAttributeItem::Attribute(attr)
+                    if matches!(
+                        &attr.name,
+                        JSXAttributeName::Identifier(ident)
+                            if ident.name == "class" || ident.name == "className"
+                    )
+            )
+        };
+        let indices: Vec<usize> = (0..attributes.len())
+            .filter(|&index| is_class(&attributes[index]))
+            .collect();
+        if indices.len() < 2 {
+            return;
+        }
+
+        let mut quasis = self.ast_builder.vec();
+        let mut expressions = self.ast_builder.vec();
+        let mut quasi = String::new();
+        for (position, &index) in indices.iter().enumerate() {
+            if position > 0 {
+                quasi.push(' ');
+            }
+            let JSXAttributeItem::Attribute(attr) = &mut attributes[index] else {
+                continue;
+            };
+            match &mut attr.value {
+                Some(JSXAttributeValue::StringLiteral(lit)) => quasi.push_str(&lit.value),
+                Some(JSXAttributeValue::ExpressionContainer(expr_container)) => {
+                    let Some(expression) = self.take_expression(expr_container) else {
+                        continue;
+                    };
+                    quasis.push(self.create_template_element(&quasi, false));
+                    quasi.clear();
+                    expressions.push(self.ast_builder.expression_logical(
+                        Span::default(),
+                        expression,
+                        LogicalOperator::Or,
+                        self.create_string_literal(""),
+                    ));
                 }
+                _ => {}
+            }
+        }
 
-                html.push('>');
+        let value = if expressions.is_empty() {
+            self.ast_builder.jsx_attribute_value_string_literal(
+                Span::default(),
+                self.ast_builder.atom(&quasi),
+                None,
+            )
+        } else {
+            quasis.push(self.create_template_element(&quasi, true));
+            let template =
+                self.ast_builder
+                    .expression_template_literal(Span::default(), quasis, expressions);
+            self.ast_builder
+                .jsx_attribute_value_expression_container(Span::default(), template.into())
+        };
+        if let JSXAttributeItem::Attribute(first) = &mut attributes[indices[0]] {
+            first.value = Some(value);
+        }
+        for &index in indices[1..].iter().rev() {
+            attributes.remove(index);
+        }
+    }
 
-                // Add children
-                for child in &element.children {
-                    match child {
-                        JSXChild::Text(text) => {
-                            let text_content = text.value.trim();
-                            if !text_content.is_empty() {
-                                html.push_str(text_content);
-                            }
-                        }
-                        JSXChild::Element(child_element) => {
-                            let (child_html, _child_dynamics) =
-                                self.extract_template_with_dynamics(child_element);
-                            html.push_str(&child_html);
-                            // TODO: Merge child dynamics with current dynamics
-                        }
-                        JSXChild::ExpressionContainer(_expr_container) => {
-                            // For now, skip dynamic expressions in templates
-                            // This will be enhanced in future iterations
-                        }
-                        _ => {} // Handle other child types if needed
+    /// Build `_$spread(_el$, props, isSVG, hasChildren)` for an element with spreads
+    ///
+    /// Universal output drops `isSVG`: `_$spread(_el$, props, hasChildren)`.
+    ///
+    /// Marks every attribute folded into the spread props in `handled`.
+    pub(crate) fn transform_spread_attributes(
+        &mut self,
+        attributes: &mut oxc_allocator::Vec<'a, JSXAttributeItem<'a>>,
+        handled: &mut [bool],
+        element: Option<&str>,
+        is_svg: bool,
+        has_children: bool,
+    ) -> Option<Statement<'a>> {
+        let element = element?;
+        let mut segments = Vec::new();
+        let mut dynamic_spread = false;
+        let mut first_spread = false;
+        let mut properties = self.ast_builder.vec();
+
+        for (index, attr) in attributes.iter_mut().enumerate() {
+            let attr = match attr {
+                JSXAttributeItem::SpreadAttribute(spread) => {
+                    first_spread = true;
+                    handled[index] = true;
+                    if !properties.is_empty() {
+                        let properties = std::mem::replace(&mut properties, self.ast_builder.vec());
+                        segments.push(
+                            self.ast_builder
+                                .expression_object(Span::default(), properties),
+                        );
+                    }
+                    let argument = spread.argument.take_in(self.allocator);
+                    if AstUtils::is_dynamic_expression(&argument, true) {
+                        dynamic_spread = true;
+                        segments.push(self.create_dynamic_spread(argument));
+                    } else {
+                        segments.push(argument);
                     }
+                    continue;
                 }
-
-                // Add closing tag if not self-closing
-                if element.closing_element.is_some() {
-                    html.push_str("</");
-                    html.push_str(tag_name);
-                    html.push('>');
+                JSXAttributeItem::Attribute(attr) => attr,
+            };
+
+            let name = Self::attribute_name(&attr.name);
+            let dynamic_value = match &attr.value {
+                Some(JSXAttributeValue::ExpressionContainer(expr_container)) => expr_container
+                    .expression
+                    .as_expression()
+                    .is_some_and(|expr| AstUtils::is_dynamic_expression(expr, true)),
+                _ => false,
+            };
+            if !(first_spread || dynamic_value) || !Self::can_native_spread(&name) {
+                continue;
+            }
+            handled[index] = true;
+
+            let value = match &mut attr.value {
+                None if PROPERTIES.contains(&name.as_str())
+                    || BOOLEAN_ATTRIBUTES.contains(&name.as_str()) =>
+                {
+                    self.ast_builder
+                        .expression_boolean_literal(Span::default(), true)
                 }
-
-                (html, dynamic_attributes)
+                None => self
+                    .ast_builder
+                    .expression_string_literal(Span::default(), "", None),
+                Some(JSXAttributeValue::StringLiteral(lit)) => {
+                    self.create_string_literal(&TemplateGenerator::decode_html_entities(&lit.value))
+                }
+                Some(JSXAttributeValue::ExpressionContainer(expr_container)) => {
+                    let Some(expression) = self.take_expression(expr_container) else {
+                        continue;
+                    };
+                    expression
+                }
+                Some(_) => continue,
+            };
+            if dynamic_value {
+                let body = self.ast_builder.vec1(
+                    self.ast_builder
+                        .statement_return(Span::default(), Some(value)),
+                );
+                properties.push(self.create_getter(&name, body));
+            } else {
+                properties.push(self.create_property(&name, value));
             }
-            _ => ("<!-- unsupported JSX -->".to_string(), dynamic_attributes),
         }
-    }
+        if !properties.is_empty() {
+            segments.push(
+                self.ast_builder
+                    .expression_object(Span::default(), properties),
+            );
+        }
 
-    /// Extract template HTML, dynamic attributes, and text insertions from a JSX element
-    fn extract_template_with_dynamics_and_text(
-        &self,
-        element: &oxc_ast::ast::JSXElement,
-    ) -> (String, Vec<DynamicAttribute>, Vec<TextInsertion>) {
-        use oxc_ast::ast::{
-            JSXAttributeItem, JSXAttributeName, JSXAttributeValue, JSXChild, JSXElementName,
+        let props = if segments.len() == 1 && !dynamic_spread {
+            segments.remove(0)
+        } else {
+            self.register_import("mergeProps");
+            self.create_call("_$mergeProps", segments)
         };
+        let spread_helper = self.register_import("spread");
+        let mut arguments = vec![self.create_identifier(element), props];
+        // Custom renderers have no SVG namespace to switch into
+        if !self.universal {
+            arguments.push(
+                self.ast_builder
+                    .expression_boolean_literal(Span::default(), is_svg),
+            );
+        }
+        arguments.push(
+            self.ast_builder
+                .expression_boolean_literal(Span::default(), has_children),
+        );
+        let spread = self.create_call(&spread_helper, arguments);
+        Some(
+            self.ast_builder
+                .statement_expression(Span::default(), spread),
+        )
+    }
 
-        let mut dynamic_attributes = Vec::new();
-        let mut text_insertions = Vec::new();
+    /// Whether an attribute can be handed to the runtime `spread`
+    ///
+    /// Refs and compile-time namespaces like `use:` need the compiler.
+    fn can_native_spread(name: &str) -> bool {
+        name != "ref" && (!name.contains(':') || name.starts_with("on:"))
+    }
 
-        match &element.opening_element.name {
-            JSXElementName::Identifier(ident) => {
-                let tag_name = &ident.name;
-                let mut html = String::new();
+    /// Attribute name as written, with any namespace prefix (`xlink:href`)
+    pub(crate) fn attribute_name(name: &JSXAttributeName<'a>) -> String {
+        match name {
+            JSXAttributeName::Identifier(ident) => ident.name.to_string(),
+            JSXAttributeName::NamespacedName(name) => {
+                format!("{}:{}", name.namespace.name, name.name.name)
+            }
+        }
+    }
 
-                // Start opening tag
-                html.push('<');
-                html.push_str(tag_name);
+    /// Namespace URI for a prefixed attribute like `xlink:href`
+    fn attribute_namespace(name: &str) -> Option<&'static str> {
+        let (prefix, _) = name.split_once(':')?;
+        SVG_NAMESPACES
+            .iter()
+            .find(|(namespace, _)| *namespace == prefix)
+            .map(|(_, uri)| *uri)
+    }
 
-                // Add attributes (same as existing logic)
-                for attr in &element.opening_element.attributes {
-                    match attr {
-                        JSXAttributeItem::Attribute(attr) => {
-                            if let JSXAttributeName::Identifier(name_ident) = &attr.name {
-                                let attr_name = &name_ident.name;
-                                let attr_name = if attr_name == "for" { "for" } else { attr_name };
-
-                                match &attr.value {
-                                    Some(JSXAttributeValue::StringLiteral(lit)) => {
-                                        html.push(' ');
-                                        html.push_str(attr_name);
-                                        html.push('=');
-                                        let value = &lit.value;
-                                        if self.needs_quotes(value) {
-                                            html.push('"');
-                                            html.push_str(value);
-                                            html.push('"');
-                                        } else {
-                                            html.push_str(value);
-                                        }
-                                    }
-                                    Some(JSXAttributeValue::ExpressionContainer(
-                                        expr_container,
-                                    )) => {
-                                        let expression_str =
-                                            self.extract_expression_string(expr_container);
-                                        let is_style_object =
-                                            attr_name == "style" && expression_str.starts_with('{');
-                                        let is_class_list = attr_name == "classList";
-                                        let requires_effect =
-                                            self.expression_requires_effect(&expression_str);
-
-                                        let dynamic_attr = DynamicAttribute {
-                                            name: attr_name.to_string(),
-                                            expression: expression_str,
-                                            is_style_object,
-                                            is_class_list,
-                                            requires_effect,
-                                        };
-                                        dynamic_attributes.push(dynamic_attr);
-                                    }
-                                    None => {
-                                        html.push(' ');
-                                        html.push_str(attr_name);
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-                        _ => {}
+    /// Write a single attribute into the template or apply it at runtime
+    fn transform_attribute(
+        &mut self,
+        attr: &mut JSXAttribute<'a>,
+        info: &mut TemplateInfo<'a>,
+    ) -> Result<(), TransformError> {
+        let name = Self::attribute_name(&attr.name);
+
+        match &mut attr.value {
+            None => self.push_template_attribute(&mut info.html, &name, ""),
+            Some(JSXAttributeValue::StringLiteral(lit))
+                if !CHILD_PROPERTIES.contains(&name.as_str()) =>
+            {
+                let value = TemplateGenerator::decode_html_entities(&lit.value);
+                self.push_template_attribute(&mut info.html, &name, &value);
+            }
+            Some(JSXAttributeValue::StringLiteral(lit)) => {
+                let value = self
+                    .create_string_literal(&TemplateGenerator::decode_html_entities(&lit.value));
+                self.push_attribute(info, name, value);
+            }
+            Some(JSXAttributeValue::ExpressionContainer(expr_container)) if name == "ref" => {
+                let (Some(element), Some(expression)) =
+                    (info.id.clone(), self.take_expression(expr_container))
+                else {
+                    return Ok(());
+                };
+                let statements = self.create_ref(expression, &element);
+                info.exprs.splice(0..0, statements);
+            }
+            Some(JSXAttributeValue::ExpressionContainer(expr_container))
+                if EventTransformer::is_event_attribute(&name)
+                    && (matches!(attr.name, JSXAttributeName::Identifier(_))
+                        || EventTransformer::is_listener_attribute(&name))
+                    && expr_container
+                        .expression
+                        .as_expression()
+                        .is_some_and(|handler| {
+                            !matches!(
+                                handler,
+                                Expression::StringLiteral(_) | Expression::NumericLiteral(_)
+                            )
+                        }) =>
+            {
+                self.transform_event(attr, info)?;
+            }
+            Some(JSXAttributeValue::ExpressionContainer(expr_container)) => {
+                let static_value = expr_container
+                    .expression
+                    .as_expression()
+                    .and_then(AstUtils::get_static_value);
+                if let Some(value) = static_value {
+                    if !CHILD_PROPERTIES.contains(&name.as_str()) {
+                        self.push_template_attribute(&mut info.html, &name, &value);
+                        return Ok(());
                     }
                 }
-
-                // Check if self-closing
-                if element.closing_element.is_none() && element.children.is_empty() {
-                    html.push('>');
-                    return (html, dynamic_attributes, text_insertions);
+                if let Some(expression) = self.take_expression(expr_container) {
+                    self.push_attribute(info, name, expression);
                 }
+            }
+            Some(_) => {}
+        }
+        Ok(())
+    }
 
-                html.push('>');
+    /// Bind an `on*`, `on:` or `oncapture:` handler, ahead of the element's other runtime code
+    fn transform_event(
+        &mut self,
+        attr: &mut JSXAttribute<'a>,
+        info: &mut TemplateInfo<'a>,
+    ) -> Result<(), EventError> {
+        let Some(element) = info.id.clone() else {
+            return Ok(());
+        };
+        let events = EventTransformer::new();
+        info.has_hydratable_event = true;
+        let bindings = match events.transform_event_handler(self, attr)? {
+            EventHandling::Delegated {
+                event_name,
+                handler,
+                data,
+            } => events.generate_delegated_binding(self, &element, &event_name, handler, data)?,
+            EventHandling::Direct {
+                event_name,
+                handler,
+                data,
+            } => events.generate_direct_binding(self, &element, &event_name, handler, data)?,
+            EventHandling::Listener {
+                event_name,
+                handler,
+                options,
+            } => events.generate_listener_binding(self, &element, &event_name, handler, options)?,
+        };
+        info.exprs.splice(0..0, bindings);
+        Ok(())
+    }
 
-                // Process children with text interpolation support
-                self.process_children_with_text_interpolation(
-                    &element.children,
-                    &mut html,
-                    &mut text_insertions,
-                );
+    /// Apply an attribute at runtime, inside an effect when it is dynamic
+    fn push_attribute(
+        &mut self,
+        info: &mut TemplateInfo<'a>,
+        name: String,
+        expression: Expression<'a>,
+    ) {
+        let Some(element) = info.id.clone() else {
+            return;
+        };
+        let attribute = DynamicAttribute {
+            name,
+            element,
+            requires_effect: self.expression_requires_effect(&expression),
+            expression,
+        };
+        if attribute.requires_effect {
+            info.dynamic_attributes.push(attribute);
+        } else {
+            let call = self.create_attribute_call(attribute, None);
+            info.exprs
+                .push(self.ast_builder.statement_expression(Span::default(), call));
+        }
+    }
 
-                // Add closing tag if not self-closing
-                if element.closing_element.is_some() {
-                    html.push_str("</");
-                    html.push_str(tag_name);
-                    html.push('>');
-                }
+    /// Hand the element to its `ref`, like dom-expressions
+    ///
+    /// Inline functions and constant bindings are called through `_$use`.
+    /// Other assignable refs are called when they hold a function and assigned
+    /// the element otherwise; anything else is only called if it is a function.
+    fn create_ref(&mut self, expression: Expression<'a>, element: &str) -> Vec<Statement<'a>> {
+        let use_helper = self.register_import("use");
+        let is_constant = matches!(
+            &expression,
+            Expression::Identifier(ident) if ident
+                .reference_id
+                .get()
+                .is_some_and(|reference_id| self.constant_references.contains(&reference_id))
+        );
+        if is_constant
+            || matches!(
+                expression.without_parentheses(),
+                Expression::FunctionExpression(_) | Expression::ArrowFunctionExpression(_)
+            )
+        {
+            let call = self.create_call(
+                &use_helper,
+                vec![expression, self.create_identifier(element)],
+            );
+            return vec![self.ast_builder.statement_expression(Span::default(), call)];
+        }
 
-                (html, dynamic_attributes, text_insertions)
+        // var _ref$ = expr;
+        // typeof _ref$ === "function" ? _$use(_ref$, _el$) : (expr = _el$);
+        let ref_id = self.generate_uid("ref$");
+        let target = match expression.clone_in(self.allocator) {
+            Expression::Identifier(ident) => {
+                Some(AssignmentTarget::AssignmentTargetIdentifier(ident))
             }
-            _ => (
-                "<!-- unsupported JSX -->".to_string(),
-                dynamic_attributes,
-                text_insertions,
+            expression => MemberExpression::try_from(expression)
+                .ok()
+                .map(|member| AssignmentTarget::from(SimpleAssignmentTarget::from(member))),
+        };
+        let declaration =
+            self.create_var_declaration(vec![self.create_declarator(&ref_id, expression)]);
+        let is_function = self.ast_builder.expression_binary(
+            Span::default(),
+            self.ast_builder.expression_unary(
+                Span::default(),
+                UnaryOperator::Typeof,
+                self.create_identifier(&ref_id),
             ),
+            BinaryOperator::StrictEquality,
+            self.create_string_literal("function"),
+        );
+        let call = self.create_call(
+            &use_helper,
+            vec![
+                self.create_identifier(&ref_id),
+                self.create_identifier(element),
+            ],
+        );
+        let statement = match target {
+            Some(target) => {
+                let assignment = self.ast_builder.expression_assignment(
+                    Span::default(),
+                    AssignmentOperator::Assign,
+                    target,
+                    self.create_identifier(element),
+                );
+                self.ast_builder.expression_conditional(
+                    Span::default(),
+                    is_function,
+                    call,
+                    assignment,
+                )
+            }
+            None => self.ast_builder.expression_logical(
+                Span::default(),
+                is_function,
+                LogicalOperator::And,
+                call,
+            ),
+        };
+        vec![
+            declaration,
+            self.ast_builder
+                .statement_expression(Span::default(), statement),
+        ]
+    }
+
+    /// Append ` name=value` to template HTML, quoting only when needed
+    fn push_template_attribute(&self, html: &mut String, name: &str, value: &str) {
+        let name = ATTRIBUTE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map_or(name, |(_, attribute)| attribute);
+        html.push(' ');
+        html.push_str(name);
+        if value.is_empty() {
+            return;
+        }
+        html.push('=');
+        let value = TemplateGenerator::escape_html(value, true);
+        if self.needs_quotes(&value) {
+            html.push('"');
+            html.push_str(&value);
+            html.push('"');
+        } else {
+            html.push_str(&value);
         }
     }
 
-    /// Process JSX children and handle text interpolation
-    fn process_children_with_text_interpolation(
-        &self,
-        children: &[oxc_ast::ast::JSXChild],
-        html: &mut String,
-        text_insertions: &mut Vec<TextInsertion>,
-    ) {
-        use oxc_ast::ast::JSXChild;
+    /// Lower element children, wiring up references and insertions
+    ///
+    /// Follows dom-expressions' `transformChildren`: referenced children are
+    /// declared by walking `firstChild`/`nextSibling` from the previous
+    /// referenced node, and their own declarations and effects are merged in.
+    fn transform_children(
+        &mut self,
+        children: &mut oxc_allocator::Vec<'a, JSXChild<'a>>,
+        info: &mut TemplateInfo<'a>,
+    ) -> Result<(), TransformError> {
+        let filtered: Vec<usize> = (0..children.len())
+            .filter(|&index| Self::is_significant_child(&children[index]))
+            .collect();
+        let (skip_ids, multi) = {
+            let filtered_children: Vec<&JSXChild<'a>> =
+                filtered.iter().map(|&index| &children[index]).collect();
+            let skip_ids: Vec<bool> = (0..filtered_children.len())
+                .map(|index| {
+                    info.id.is_none() || !self.detect_expressions(&filtered_children, index)
+                })
+                .collect();
+            (skip_ids, Self::check_length(&filtered_children))
+        };
 
-        let mut has_leading_text = false;
-        let mut insertion_counter = 0;
+        let mut nodes: Vec<ChildNode<'a>> = Vec::new();
+        for (&index, &skip_id) in filtered.iter().zip(&skip_ids) {
+            let Some(node) = self.transform_node(&mut children[index], skip_id)? else {
+                continue;
+            };
+            if let (ChildNode::Template(current), Some(ChildNode::Template(previous))) =
+                (&node, nodes.last_mut())
+            {
+                if current.is_text && previous.is_text {
+                    previous.html.push_str(&current.html);
+                    continue;
+                }
+            }
+            nodes.push(node);
+        }
 
-        for (index, child) in children.iter().enumerate() {
-            match child {
-                JSXChild::Text(text) => {
-                    let text_content = text.value.as_str();
-                    let trimmed = text_content.trim();
-
-                    if !trimmed.is_empty() {
-                        html.push_str(trimmed);
-                        has_leading_text = true;
-                    } else if !text_content.is_empty() {
-                        // Handle whitespace-only text nodes
-                        let normalized_space =
-                            if text_content.contains('\n') || text_content.len() > 1 {
-                                " "
-                            } else {
-                                text_content
-                            };
-                        html.push_str(normalized_space);
-                        has_leading_text = true;
+        let next_ids: Vec<Option<String>> = (0..nodes.len())
+            .map(|index| {
+                nodes[index + 1..].iter().find_map(|node| match node {
+                    ChildNode::Template(child) => child.id.clone(),
+                    ChildNode::Insertion(_) => None,
+                })
+            })
+            .collect();
+        let wrapped: Vec<bool> = (0..nodes.len())
+            .map(|index| Self::wrapped_by_text(&nodes, index))
+            .collect();
+
+        let mut temp_path = info.id.clone().unwrap_or_default();
+        let mut walked = 0;
+        let mut next_placeholder: Option<String> = None;
+        for (index, node) in nodes.into_iter().enumerate() {
+            match node {
+                ChildNode::Template(child) => {
+                    info.html.push_str(&child.html);
+                    if let Some(id) = child.id {
+                        let walk = self.create_walk(&temp_path, walked);
+                        info.declarations.push(self.create_declarator(&id, walk));
+                        temp_path = id;
+                        walked += 1;
                     }
+                    info.declarations.extend(child.declarations);
+                    info.exprs.extend(child.exprs);
+                    info.dynamic_attributes.extend(child.dynamic_attributes);
+                    info.has_hydratable_event |= child.has_hydratable_event;
+                    next_placeholder = None;
                 }
-                JSXChild::ExpressionContainer(expr_container) => {
-                    // This is a text interpolation - extract the expression
-                    let expression_str = self.extract_expression_string(expr_container.as_ref());
-
-                    // Add marker to template for insertion point
-                    if index == children.len() - 1 {
-                        // Last expression - insert at end
-                        text_insertions.push(TextInsertion {
-                            expression: expression_str,
-                            position: InsertionPosition::AtEnd,
-                        });
-                    } else if !has_leading_text && index == 0 {
-                        // First expression with no leading text - insert before first node
-                        text_insertions.push(TextInsertion {
-                            expression: expression_str,
-                            position: InsertionPosition::BeforeNode("_el$.firstChild".to_string()),
-                        });
+                ChildNode::Insertion(expression) => {
+                    let Some(parent) = info.id.clone() else {
+                        continue;
+                    };
+                    let position = if self.options.hydratable && multi {
+                        // Server-rendered content sits between `<!$>` and `<!/>`
+                        // comments, which `_$getNextMarker` claims on hydration
+                        self.register_import("insert");
+                        let start = self.generate_uid("el$");
+                        let walk = self.create_walk(&temp_path, walked);
+                        info.declarations.push(self.create_declarator(&start, walk));
+                        walked += 1;
+                        let end = self.generate_uid("el$");
+                        let content = self.generate_uid("co$");
+                        info.html.push_str("<!$><!/>");
+                        info.declarations
+                            .push(self.create_marker_declarator(&start, &end, &content));
+                        walked += 1;
+                        temp_path = end.clone();
+                        InsertionPosition::Marker(end, content)
+                    } else if wrapped[index] {
+                        // Text on both sides would merge into one DOM node, so the
+                        // expression is anchored to a `<!>` comment between them
+                        let marker = match next_placeholder.take() {
+                            Some(marker) => marker,
+                            None => {
+                                let marker = self.generate_uid("el$");
+                                info.html.push_str("<!>");
+                                let walk = self.create_walk(&temp_path, walked);
+                                info.declarations
+                                    .push(self.create_declarator(&marker, walk));
+                                walked += 1;
+                                marker
+                            }
+                        };
+                        temp_path = marker.clone();
+                        next_placeholder = Some(marker.clone());
+                        InsertionPosition::BeforeNode(marker)
+                    } else if !multi {
+                        InsertionPosition::Replace
+                    } else if let Some(next_id) = &next_ids[index] {
+                        InsertionPosition::BeforeNode(next_id.clone())
                     } else {
-                        // Expression in the middle - need placeholder
-                        html.push_str(" ");
-                        text_insertions.push(TextInsertion {
-                            expression: expression_str,
-                            position: InsertionPosition::AtEnd,
-                        });
-                    }
+                        InsertionPosition::AtEnd
+                    };
+                    let insert = self.create_insert(TextInsertion {
+                        parent,
+                        expression,
+                        position,
+                    });
+                    info.exprs.push(
+                        self.ast_builder
+                            .statement_expression(Span::default(), insert),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the insertion at `index` sits between two text nodes
+    ///
+    /// Mirrors dom-expressions' `wrappedByText`; referenced elements in between
+    /// already separate the text, so they stop the search.
+    fn wrapped_by_text(nodes: &[ChildNode<'a>], index: usize) -> bool {
+        let is_text_boundary = |node: &ChildNode<'a>| match node {
+            ChildNode::Template(child) if child.is_text => Some(true),
+            ChildNode::Template(child) if child.id.is_some() => Some(false),
+            _ => None,
+        };
+        nodes[..index].iter().rev().find_map(is_text_boundary) == Some(true)
+            && nodes[index + 1..].iter().find_map(is_text_boundary) == Some(true)
+    }
 
-                    insertion_counter += 1;
+    /// Step from `temp_path` to the next child: `firstChild` for the first
+    /// reference under a parent, `nextSibling` after that
+    fn create_walk(&self, temp_path: &str, walked: usize) -> Expression<'a> {
+        let step = if walked == 0 {
+            "firstChild"
+        } else {
+            "nextSibling"
+        };
+        self.create_member_path(&format!("{}.{}", temp_path, step))
+    }
+
+    /// Lower a single JSX child
+    ///
+    /// Returns `None` for children that produce nothing, such as whitespace.
+    fn transform_node(
+        &mut self,
+        child: &mut JSXChild<'a>,
+        skip_id: bool,
+    ) -> Result<Option<ChildNode<'a>>, TransformError> {
+        Ok(match child {
+            JSXChild::Element(element) => {
+                let tag_name = AstUtils::get_element_name(element).unwrap_or_default();
+                if AstUtils::is_component(&tag_name) {
+                    // Components are inserted as created, without a thunk
+                    let component =
+                        ComponentTransformer::new().transform_custom_component(self, element)?;
+                    return Ok(Some(ChildNode::Insertion(component)));
                 }
-                JSXChild::Element(child_element) => {
-                    let (child_html, _child_dynamics, _child_insertions) =
-                        self.extract_template_with_dynamics_and_text(child_element);
-                    html.push_str(&child_html);
+                if self.is_foreign_element(&tag_name) {
+                    let expression = self.transform_jsx_element(element)?;
+                    return Ok(Some(ChildNode::Insertion(expression)));
                 }
-                _ => {}
+                Some(ChildNode::Template(
+                    self.transform_element(element, skip_id)?,
+                ))
             }
+            JSXChild::Text(text) => {
+                let raw = text.raw.unwrap_or(text.value);
+                let html = TemplateGenerator::trim_whitespace(&raw);
+                if html.is_empty() {
+                    return Ok(None);
+                }
+                let id = (!skip_id).then(|| self.generate_uid("el$"));
+                Some(ChildNode::Template(TemplateInfo::text(html, id)))
+            }
+            JSXChild::ExpressionContainer(expr_container) => {
+                let static_value = expr_container
+                    .expression
+                    .as_expression()
+                    .and_then(AstUtils::get_static_value);
+                if let Some(value) = static_value {
+                    let id = (!skip_id).then(|| self.generate_uid("el$"));
+                    let html = TemplateGenerator::escape_html(&value, false);
+                    return Ok(Some(ChildNode::Template(TemplateInfo::text(html, id))));
+                }
+                self.take_expression(expr_container)
+                    .map(|expression| ChildNode::Insertion(self.create_insert_value(expression)))
+            }
+            // Compiled when the generated insert is visited
+            JSXChild::Fragment(fragment) => Some(ChildNode::Insertion(Expression::JSXFragment(
+                fragment.take_in_box(self.allocator),
+            ))),
+            _ => None,
+        })
+    }
+
+    /// Mirrors dom-expressions' `filterChildren`: drops empty expressions and
+    /// whitespace-only text that starts on a new line
+    pub(crate) fn is_significant_child(child: &JSXChild<'a>) -> bool {
+        match child {
+            JSXChild::ExpressionContainer(expr_container) => {
+                expr_container.expression.as_expression().is_some()
+            }
+            JSXChild::Text(text) => {
+                let raw = text.raw.unwrap_or(text.value);
+                !(raw.starts_with(['\r', '\n']) && raw.trim().is_empty())
+            }
+            _ => true,
         }
     }
 
-    /// Extract expression as string (improved for Phase 2)
-    fn extract_expression_string(&self, expr_container: &JSXExpressionContainer) -> String {
-        use oxc_ast::ast::JSXExpression;
+    /// Whether a children list renders more than one node, so inserts need a position
+    pub(crate) fn check_length(children: &[&JSXChild<'a>]) -> bool {
+        children
+            .iter()
+            .filter(|child| match child {
+                JSXChild::Text(text) => {
+                    let raw = text.raw.unwrap_or(text.value);
+                    !raw.trim().is_empty() || raw.chars().all(|ch| ch == ' ')
+                }
+                _ => true,
+            })
+            .count()
+            > 1
+    }
 
-        match &expr_container.expression {
-            JSXExpression::Identifier(ident) => ident.name.to_string(),
-            JSXExpression::StringLiteral(str_lit) => format!("\"{}\"", str_lit.value),
-            JSXExpression::NumericLiteral(num_lit) => num_lit.value.to_string(),
-            JSXExpression::BinaryExpression(_) => "expr".to_string(), // Placeholder for complex expressions
-            JSXExpression::CallExpression(_) => "expr()".to_string(), // Placeholder for function calls
-            _ => "expr".to_string(), // Fallback for other expression types
+    /// Whether a child is inserted at runtime rather than written into the template
+    fn is_expression_child(&self, child: &JSXChild<'a>) -> bool {
+        match child {
+            JSXChild::ExpressionContainer(expr_container) => expr_container
+                .expression
+                .as_expression()
+                .is_some_and(|expr| AstUtils::get_static_value(expr).is_none()),
+            JSXChild::Element(element) => {
+                AstUtils::get_element_name(element).is_some_and(|tag_name| {
+                    AstUtils::is_component(&tag_name) || self.is_foreign_element(&tag_name)
+                })
+            }
+            JSXChild::Fragment(_) => true,
+            _ => false,
         }
     }
 
-    /// Check if attribute value needs quotes (simple heuristic)
+    /// Whether the child at `index`, or anything after it, needs a reference
+    ///
+    /// Mirrors dom-expressions' `detectExpressions`, which decides whether a
+    /// child gets an id of its own.
+    fn detect_expressions(&self, children: &[&JSXChild<'a>], index: usize) -> bool {
+        if index > 0 && self.is_expression_child(children[index - 1]) {
+            return true;
+        }
+
+        children[index..].iter().any(|child| match child {
+            _ if self.is_expression_child(child) => true,
+            JSXChild::Element(element) => {
+                let has_dynamic_attribute =
+                    element.opening_element.attributes.iter().any(|attr| match attr {
+                        JSXAttributeItem::SpreadAttribute(_) => true,
+                        JSXAttributeItem::Attribute(attr) => {
+                            let child_property = matches!(
+                                &attr.name,
+                                JSXAttributeName::Identifier(ident)
+                                    if CHILD_PROPERTIES.contains(&ident.name.as_str())
+                            );
+                            let expression_value = matches!(
+                                &attr.value,
+                                Some(JSXAttributeValue::ExpressionContainer(expr_container))
+                                    if !matches!(
+                                        expr_container.expression.as_expression(),
+                                        Some(Expression::StringLiteral(_) | Expression::NumericLiteral(_))
+                                    )
+                            );
+                            child_property || expression_value
+                        }
+                    });
+                if has_dynamic_attribute {
+                    return true;
+                }
+                let nested: Vec<&JSXChild<'a>> = element
+                    .children
+                    .iter()
+                    .filter(|child| Self::is_significant_child(child))
+                    .collect();
+                !nested.is_empty() && self.detect_expressions(&nested, 0)
+            }
+            _ => false,
+        })
+    }
+
+    /// Move the expression out of a JSX expression container
+    ///
+    /// Returns `None` for empty containers such as `{/* comment */}`.
+    pub(crate) fn take_expression(
+        &self,
+        expr_container: &mut JSXExpressionContainer<'a>,
+    ) -> Option<Expression<'a>> {
+        expr_container
+            .expression
+            .as_expression_mut()
+            .map(|expression| expression.take_in(self.allocator))
+    }
+
+    /// Check if an attribute value must be quoted in template HTML
     fn needs_quotes(&self, value: &str) -> bool {
-        // Don't quote simple alphanumeric values, but quote values with spaces or special chars
-        value.contains(' ') || value.contains('"') || value.contains('\'') || value.is_empty()
-    }
-
-    /// Add a dynamic attribute for later processing
-    pub fn add_dynamic_attribute(&mut self, attr_name: &str, expression: &str) {
-        // Analyze the attribute to determine processing strategy
-        let is_style_object = attr_name == "style" && expression.starts_with('{');
-        let is_class_list = attr_name == "classList";
-        let requires_effect = self.expression_requires_effect(expression);
-
-        let _dynamic_attr = DynamicAttribute {
-            name: attr_name.to_string(),
-            expression: expression.to_string(),
-            is_style_object,
-            is_class_list,
-            requires_effect,
-        };
+        value.is_empty()
+            || value
+                .chars()
+                .any(|ch| ch.is_whitespace() || matches!(ch, '"' | '\'' | '=' | '<' | '>' | '`'))
+    }
+
+    /// Determine if an expression requires an effect wrapper
+    fn expression_requires_effect(&self, expression: &Expression<'a>) -> bool {
+        self.options.effect_wrapper && AstUtils::is_dynamic_expression(expression, true)
+    }
+
+    /// Runtime helpers needed for current transformation, in import order
+    ///
+    /// Matches dom-expressions' ordering: `template` comes first, followed by the
+    /// other helpers from most to least recently registered.
+    pub fn get_required_imports(&self) -> Vec<RuntimeImport> {
+        let mut imports = Vec::new();
 
-        // For now, we'll store this in a temporary way
-        // TODO: Associate with specific template
-        self.required_imports.insert("setAttribute".to_string());
-        if requires_effect {
-            self.required_imports.insert("effect".to_string());
+        if !self.templates.is_empty() {
+            // Only the DOM renderer uses templates
+            let module = match self.options.generate {
+                GenerateMode::Dynamic => self
+                    .options
+                    .renderers
+                    .iter()
+                    .find(|renderer| renderer.is_dom())
+                    .map_or(&self.options.module_name, |renderer| &renderer.module_name),
+                _ => &self.options.module_name,
+            };
+            imports.push(RuntimeImport {
+                name: "template".to_string(),
+                module: module.clone(),
+                local: "_$template".to_string(),
+            });
         }
-        if is_style_object {
-            self.required_imports.insert("style".to_string());
+        imports.extend(self.required_imports.iter().rev().cloned());
+
+        imports
+    }
+
+    /// Wrap a template clone together with the code that wires up its dynamic parts
+    ///
+    /// Produces `(() => { var _el$ = _tmpl$(), _el$2 = _el$.firstChild; ...; return _el$; })()`,
+    /// or just `_tmpl$()` for a fully static template.
+    fn create_template_wrapper(
+        &mut self,
+        template_name: &str,
+        info: TemplateInfo<'a>,
+    ) -> Expression<'a> {
+        // Hydration claims the server-rendered node instead of cloning the template
+        let root = if self.options.hydratable {
+            self.register_import("getNextElement");
+            let template = self.create_identifier(template_name);
+            self.create_call("_$getNextElement", vec![template])
+        } else {
+            self.create_call(template_name, Vec::new())
+        };
+        let Some(id) = info.id.clone().filter(|_| info.has_dynamic_content()) else {
+            return root;
+        };
+
+        let mut statements = self.ast_builder.vec();
+        let mut declarators = vec![self.create_declarator(&id, root)];
+        declarators.extend(info.declarations);
+        statements.push(self.create_var_declaration(declarators));
+        statements.extend(info.exprs);
+        if let Some(effect) = self.wrap_dynamics(info.dynamic_attributes) {
+            statements.push(effect);
         }
-        if is_class_list {
-            self.required_imports.insert("classList".to_string());
+        if self.options.hydratable && info.has_hydratable_event {
+            self.register_import("runHydrationEvents");
+            let run = self.create_call("_$runHydrationEvents", Vec::new());
+            statements.push(self.ast_builder.statement_expression(Span::default(), run));
         }
+        statements.push(
+            self.ast_builder
+                .statement_return(Span::default(), Some(self.create_identifier(&id))),
+        );
+
+        self.create_iife(statements)
     }
 
-    /// Determine if an expression requires an effect wrapper
-    fn expression_requires_effect(&self, expression: &str) -> bool {
-        // Simple heuristic: if expression contains function calls, it probably needs an effect
-        expression.contains('(') && expression.contains(')')
+    /// Build `_$insert(parent, value[, marker])`
+    fn create_insert(&mut self, insertion: TextInsertion<'a>) -> Expression<'a> {
+        let insert = self.register_import("insert");
+        let mut arguments = vec![
+            self.create_identifier(&insertion.parent),
+            insertion.expression,
+        ];
+        match &insertion.position {
+            InsertionPosition::Replace => {}
+            InsertionPosition::BeforeNode(node_ref) => {
+                arguments.push(self.create_member_path(node_ref));
+            }
+            InsertionPosition::Marker(end, content) => {
+                arguments.push(self.create_identifier(end));
+                arguments.push(self.create_identifier(content));
+            }
+            InsertionPosition::AtEnd => {
+                arguments.push(self.ast_builder.expression_null_literal(Span::default()));
+            }
+        }
+        self.create_call(&insert, arguments)
     }
 
-    /// Generate runtime imports needed for current transformation
-    pub fn get_required_imports(&self) -> Vec<String> {
-        let mut imports = Vec::new();
+    /// Re-apply dynamic attributes inside a single `_$effect`
+    ///
+    /// One attribute becomes `_$effect(() => set(...))`. Several are batched like
+    /// dom-expressions' `wrapDynamics`: each value is compared with the previous
+    /// one kept on `_p$` and only changed attributes are written.
+    fn wrap_dynamics(&mut self, mut dynamics: Vec<DynamicAttribute<'a>>) -> Option<Statement<'a>> {
+        if dynamics.is_empty() {
+            return None;
+        }
+        self.register_import("effect");
+
+        if dynamics.len() == 1 {
+            let attr = dynamics.remove(0);
+            let tracks_previous = matches!(attr.name.as_str(), "style" | "classList");
+            let prev = tracks_previous.then(|| self.create_identifier("_$p"));
+            let call = self.create_attribute_call(attr, prev);
+            let params: &[&str] = if tracks_previous { &["_$p"] } else { &[] };
+            let effect = self.create_call("_$effect", vec![self.create_arrow(params, call)]);
+            return Some(
+                self.ast_builder
+                    .statement_expression(Span::default(), effect),
+            );
+        }
 
-        // Always need template
-        imports.push("template as _$template".to_string());
+        let mut declarators = Vec::new();
+        let mut statements = self.ast_builder.vec();
+        let mut properties = self.ast_builder.vec();
+        for (index, attr) in dynamics.into_iter().enumerate() {
+            let key = Self::effect_key(index);
+            let value_id = self.generate_uid("v$");
+            declarators.push(self.create_declarator(&value_id, attr.expression));
+            properties.push(
+                self.ast_builder.object_property_kind_object_property(
+                    Span::default(),
+                    PropertyKind::Init,
+                    self.ast_builder.property_key_static_identifier(
+                        Span::default(),
+                        self.ast_builder.atom(&key),
+                    ),
+                    self.create_identifier("undefined"),
+                    false,
+                    false,
+                    false,
+                ),
+            );
+
+            let previous = format!("_p$.{}", key);
+            let statement = if matches!(attr.name.as_str(), "style" | "classList") {
+                // _p$.e = _$style(_el$, _v$, _p$.e)
+                let attribute = DynamicAttribute {
+                    expression: self.create_identifier(&value_id),
+                    ..attr
+                };
+                let call =
+                    self.create_attribute_call(attribute, Some(self.create_member_path(&previous)));
+                self.create_assignment(&previous, call)
+            } else {
+                // _v$ !== _p$.e && _$setAttribute(_el$, "title", _p$.e = _v$)
+                let attribute = DynamicAttribute {
+                    expression: self
+                        .create_assignment(&previous, self.create_identifier(&value_id)),
+                    ..attr
+                };
+                let changed = self.ast_builder.expression_binary(
+                    Span::default(),
+                    self.create_identifier(&value_id),
+                    BinaryOperator::StrictInequality,
+                    self.create_member_path(&previous),
+                );
+                let call = self.create_attribute_call(attribute, None);
+                self.ast_builder.expression_logical(
+                    Span::default(),
+                    changed,
+                    LogicalOperator::And,
+                    call,
+                )
+            };
+            statements.push(
+                self.ast_builder
+                    .statement_expression(Span::default(), statement),
+            );
+        }
 
-        for import in &self.required_imports {
-            match import.as_str() {
-                "setAttribute" => imports.push("setAttribute as _$setAttribute".to_string()),
-                "effect" => imports.push("effect as _$effect".to_string()),
-                "style" => imports.push("style as _$style".to_string()),
-                "classList" => imports.push("classList as _$classList".to_string()),
-                "insert" => imports.push("insert as _$insert".to_string()),
-                _ => {}
+        statements.insert(0, self.create_var_declaration(declarators));
+        statements.push(
+            self.ast_builder
+                .statement_return(Span::default(), Some(self.create_identifier("_p$"))),
+        );
+        let effect = self.create_call(
+            "_$effect",
+            vec![
+                self.create_block_arrow(&["_p$"], statements),
+                self.ast_builder
+                    .expression_object(Span::default(), properties),
+            ],
+        );
+        Some(
+            self.ast_builder
+                .statement_expression(Span::default(), effect),
+        )
+    }
+
+    /// Property name for the `index`-th value tracked by a batched effect
+    pub(crate) fn effect_key(index: usize) -> String {
+        let keys: Vec<char> = EFFECT_KEYS.chars().collect();
+        let mut key = keys[index % keys.len()].to_string();
+        if index >= keys.len() {
+            key.push_str(&(index / keys.len()).to_string());
+        }
+        key
+    }
+
+    /// Build the runtime code that applies a single attribute to its element
+    ///
+    /// `prev` is the previous value for helpers that diff against it (`style`,
+    /// `classList`).
+    fn create_attribute_call(
+        &mut self,
+        attr: DynamicAttribute<'a>,
+        prev: Option<Expression<'a>>,
+    ) -> Expression<'a> {
+        let element = self.create_identifier(&attr.element);
+        let value = attr.expression;
+        match attr.name.as_str() {
+            "style" | "classList" => {
+                self.register_import(&attr.name);
+                let mut arguments = vec![element, value];
+                arguments.extend(prev);
+                self.create_call(&format!("_${}", attr.name), arguments)
+            }
+            "class" | "className" => {
+                self.register_import("className");
+                self.create_call("_$className", vec![element, value])
+            }
+            name if CHILD_PROPERTIES.contains(&name) || PROPERTIES.contains(&name) => {
+                self.create_assignment(&format!("{}.{}", attr.element, name), value)
+            }
+            name if Self::attribute_namespace(name).is_some() => {
+                self.register_import("setAttributeNS");
+                let namespace = Self::attribute_namespace(name).unwrap_or_default();
+                let namespace = self.ast_builder.expression_string_literal(
+                    Span::default(),
+                    self.ast_builder.atom(namespace),
+                    None,
+                );
+                let name = self.ast_builder.expression_string_literal(
+                    Span::default(),
+                    self.ast_builder.atom(name),
+                    None,
+                );
+                self.create_call("_$setAttributeNS", vec![element, namespace, name, value])
+            }
+            name => {
+                self.register_import("setAttribute");
+                let name = ATTRIBUTE_ALIASES
+                    .iter()
+                    .find(|(alias, _)| *alias == name)
+                    .map_or(name, |(_, attribute)| attribute);
+                let name = self.ast_builder.expression_string_literal(
+                    Span::default(),
+                    self.ast_builder.atom(name),
+                    None,
+                );
+                self.create_call("_$setAttribute", vec![element, name, value])
             }
         }
+    }
 
-        imports
+    /// Prepare an inserted child expression for `_$insert`
+    ///
+    /// Dynamic expressions are wrapped in a thunk so the runtime can track them;
+    /// a bare call like `count()` is passed as `count` instead of `() => count()`.
+    /// Conditionals are guarded on memoized tests, see `transform_condition`.
+    pub(crate) fn create_insert_value(&mut self, expression: Expression<'a>) -> Expression<'a> {
+        if !AstUtils::is_dynamic_expression(&expression, true) {
+            return expression;
+        }
+        if self.options.wrap_conditionals
+            && matches!(
+                expression.without_parentheses(),
+                Expression::ConditionalExpression(_) | Expression::LogicalExpression(_)
+            )
+        {
+            return self.create_condition_thunk(expression);
+        }
+        self.create_thunk(expression)
     }
 
-    /// Generate IIFE wrapper for dynamic attribute processing
-    pub fn generate_dynamic_wrapper(
-        &self,
-        template_name: &str,
-        dynamic_attrs: &[DynamicAttribute],
-    ) -> String {
-        if dynamic_attrs.is_empty() {
-            return format!("{}()", template_name);
-        }
-
-        let mut wrapper = String::new();
-        wrapper.push_str("(() => {\n");
-        wrapper.push_str(&format!("  var _el$ = {}();\n", template_name));
-
-        // Generate dynamic attribute assignments
-        for (_index, attr) in dynamic_attrs.iter().enumerate() {
-            match attr.name.as_str() {
-                "style" if attr.is_style_object => {
-                    if attr.requires_effect {
-                        wrapper.push_str(&format!(
-                            "  _$effect(_$p => _$style(_el$, {}, _$p));\n",
-                            attr.expression
-                        ));
-                    } else {
-                        wrapper.push_str(&format!("  _$style(_el$, {});\n", attr.expression));
+    /// `() => expression`, or just `count` for a bare call like `count()`
+    fn create_thunk(&self, expression: Expression<'a>) -> Expression<'a> {
+        if let Expression::CallExpression(call) = &expression {
+            if call.arguments.is_empty()
+                && matches!(call.callee, Expression::Identifier(_))
+                && !call.optional
+            {
+                let Expression::CallExpression(mut call) = expression else {
+                    unreachable!()
+                };
+                return call.callee.take_in(self.allocator);
+            }
+        }
+        self.create_arrow(&[], expression)
+    }
+
+    /// Compile a fragment to its children: one value, or an array of them
+    ///
+    /// Mirrors dom-expressions' `transformFragmentChildren`: text becomes string
+    /// literals, elements are compiled in place and dynamic expressions are
+    /// memoized so each entry tracks its own dependencies.
+    pub fn transform_fragment(
+        &mut self,
+        fragment: &mut JSXFragment<'a>,
+    ) -> Result<Expression<'a>, TransformError> {
+        let mut values = Vec::new();
+        for child in fragment.children.iter_mut() {
+            if !Self::is_significant_child(child) {
+                continue;
+            }
+            match child {
+                JSXChild::Text(text) => {
+                    let raw = text.raw.unwrap_or(text.value);
+                    let value = TemplateGenerator::decode_html_entities(
+                        &TemplateGenerator::trim_whitespace(&raw),
+                    );
+                    if !value.is_empty() {
+                        values.push(self.create_string_literal(&value));
                     }
                 }
-                "classList" => {
-                    wrapper.push_str(&format!("  _$classList(_el$, {});\n", attr.expression));
-                }
-                _ => {
-                    if attr.requires_effect {
-                        wrapper.push_str(&format!(
-                            "  _$effect(() => _$setAttribute(_el$, \"{}\", {}));\n",
-                            attr.name, attr.expression
-                        ));
-                    } else {
-                        wrapper.push_str(&format!(
-                            "  _$setAttribute(_el$, \"{}\", {});\n",
-                            attr.name, attr.expression
-                        ));
+                JSXChild::Element(element) => values.push(self.transform_jsx_element(element)?),
+                JSXChild::Fragment(fragment) => values.push(self.transform_fragment(fragment)?),
+                JSXChild::ExpressionContainer(expr_container) => {
+                    let static_value = expr_container
+                        .expression
+                        .as_expression()
+                        .and_then(AstUtils::get_static_value);
+                    if let Some(value) = static_value {
+                        values.push(self.create_string_literal(&value));
+                    } else if let Some(expression) = self.take_expression(expr_container) {
+                        values.push(self.create_fragment_value(expression));
                     }
                 }
+                JSXChild::Spread(spread) => values.push(spread.expression.take_in(self.allocator)),
             }
         }
 
-        wrapper.push_str("  return _el$;\n");
-        wrapper.push_str("})()");
-        wrapper
+        if values.len() == 1 {
+            return Ok(values.remove(0));
+        }
+        Ok(self.ast_builder.expression_array(
+            Span::default(),
+            self.ast_builder
+                .vec_from_iter(values.into_iter().map(ArrayExpressionElement::from)),
+        ))
     }
 
-    /// Generate IIFE wrapper for dynamic attribute processing and text insertions
-    pub fn generate_dynamic_wrapper_with_text(
+    /// Wrap a dynamic fragment child in `_$memo`, or a plain thunk without `memo_wrapper`
+    ///
+    /// The memo re-runs the whole expression, so conditionals are guarded inline.
+    /// Server output needs no tracking and keeps the expression as is.
+    fn create_fragment_value(&mut self, mut expression: Expression<'a>) -> Expression<'a> {
+        if self.options.generate_ssr || !AstUtils::is_dynamic_expression(&expression, true) {
+            return expression;
+        }
+        self.transform_condition(&mut expression);
+        let thunk = self.create_thunk(expression);
+        if !self.options.memo_wrapper {
+            return thunk;
+        }
+        self.register_import("memo");
+        self.create_call("_$memo", vec![thunk])
+    }
+
+    /// Create a string literal expression
+    pub(crate) fn create_string_literal(&self, value: &str) -> Expression<'a> {
+        self.ast_builder.expression_string_literal(
+            Span::default(),
+            self.ast_builder.atom(value),
+            None,
+        )
+    }
+
+    /// Thunk a conditional child, hoisting its outermost guarded test
+    ///
+    /// `_c$() ? a() : b` only re-runs when the memoized test flips, so the
+    /// output is `(() => { var _c$ = _$memo(() => !!test); return () => ...; })()`.
+    fn create_condition_thunk(&mut self, mut expression: Expression<'a>) -> Expression<'a> {
+        let Some((id, test)) = self.guard_condition(&mut expression, false) else {
+            return self.create_arrow(&[], expression);
+        };
+        let test = self.create_arrow(&[], test);
+        let init = if self.options.memo_wrapper {
+            self.register_import("memo");
+            self.create_call("_$memo", vec![test])
+        } else {
+            test
+        };
+        let mut statements = self.ast_builder.vec();
+        statements.push(self.create_var_declaration(vec![self.create_declarator(&id, init)]));
+        statements.push(
+            self.ast_builder
+                .statement_return(Span::default(), Some(self.create_arrow(&[], expression))),
+        );
+        self.create_iife(statements)
+    }
+
+    /// Guard a conditional's tests in place with `_$memo(() => !!test)()`
+    ///
+    /// Used where the whole expression is already re-evaluated lazily, such as
+    /// component prop getters. Server output is left as is.
+    pub(crate) fn transform_condition(&mut self, expression: &mut Expression<'a>) {
+        if self.options.wrap_conditionals && !self.options.generate_ssr {
+            self.guard_condition(expression, true);
+        }
+    }
+
+    /// Mirrors dom-expressions' `transformCondition`
+    ///
+    /// A test is memoized only when it is reactive and guards a reactive branch
+    /// (for `&&`/`||` chains, the first `&&` from the left). Nested conditionals
+    /// in the branches are guarded inline. Unless `inline`, the outermost test is
+    /// replaced by a call to a fresh `_c$` and returned with it for hoisting.
+    fn guard_condition(
         &mut self,
-        template_name: &str,
-        dynamic_attrs: &[DynamicAttribute],
-        text_insertions: &[TextInsertion],
-    ) -> String {
-        if dynamic_attrs.is_empty() && text_insertions.is_empty() {
-            return format!("{}()", template_name);
-        }
-
-        let mut wrapper = String::new();
-        wrapper.push_str("(() => {\n");
-        wrapper.push_str(&format!("  var _el$ = {}();\n", template_name));
-
-        // Generate text insertion calls first
-        for insertion in text_insertions {
-            match &insertion.position {
-                InsertionPosition::AtEnd => {
-                    wrapper.push_str(&format!(
-                        "  _$insert(_el$, {}, null);\n",
-                        insertion.expression
-                    ));
-                }
-                InsertionPosition::BeforeNode(node_ref) => {
-                    wrapper.push_str(&format!(
-                        "  _$insert(_el$, {}, {});\n",
-                        insertion.expression, node_ref
-                    ));
+        expression: &mut Expression<'a>,
+        inline: bool,
+    ) -> Option<(String, Expression<'a>)> {
+        if inline && !self.options.memo_wrapper {
+            return None;
+        }
+        match expression {
+            Expression::ParenthesizedExpression(parenthesized) => {
+                self.guard_condition(&mut parenthesized.expression, inline)
+            }
+            Expression::ConditionalExpression(conditional) => {
+                let guards_dynamic =
+                    AstUtils::is_dynamic_expression_with_tags(&conditional.consequent, false, true)
+                        || AstUtils::is_dynamic_expression_with_tags(
+                            &conditional.alternate,
+                            false,
+                            true,
+                        );
+                if !guards_dynamic || !AstUtils::is_dynamic_expression(&conditional.test, true) {
+                    return None;
                 }
-                InsertionPosition::AfterNode(node_ref) => {
-                    wrapper.push_str(&format!(
-                        "  _$insert(_el$, {}, {}.nextSibling);\n",
-                        insertion.expression, node_ref
-                    ));
+                let test = conditional.test.take_in(self.allocator);
+                let (guard, hoisted) = self.memoize_test(test, inline);
+                conditional.test = guard;
+                self.guard_condition(&mut conditional.consequent, true);
+                self.guard_condition(&mut conditional.alternate, true);
+                hoisted
+            }
+            Expression::LogicalExpression(_) => {
+                let logical = Self::leading_logical(expression)?;
+                if logical.operator != LogicalOperator::And
+                    || !AstUtils::is_dynamic_expression_with_tags(&logical.right, false, true)
+                    || !AstUtils::is_dynamic_expression(&logical.left, true)
+                {
+                    return None;
                 }
+                let test = logical.left.take_in(self.allocator);
+                let (guard, hoisted) = self.memoize_test(test, inline);
+                logical.left = guard;
+                hoisted
             }
+            _ => None,
         }
+    }
 
-        // Generate dynamic attribute assignments
-        for (_index, attr) in dynamic_attrs.iter().enumerate() {
-            match attr.name.as_str() {
-                "style" if attr.is_style_object => {
-                    if attr.requires_effect {
-                        wrapper.push_str(&format!(
-                            "  _$effect(_$p => _$style(_el$, {}, _$p));\n",
-                            attr.expression
-                        ));
-                    } else {
-                        wrapper.push_str(&format!("  _$style(_el$, {});\n", attr.expression));
-                    }
-                }
-                "classList" => {
-                    wrapper.push_str(&format!("  _$classList(_el$, {});\n", attr.expression));
-                }
-                _ => {
-                    if attr.requires_effect {
-                        wrapper.push_str(&format!(
-                            "  _$effect(() => _$setAttribute(_el$, \"{}\", {}));\n",
-                            attr.name, attr.expression
-                        ));
-                    } else {
-                        wrapper.push_str(&format!(
-                            "  _$setAttribute(_el$, \"{}\", {});\n",
-                            attr.name, attr.expression
-                        ));
-                    }
+    /// The logical expression whose left side is a condition's test
+    ///
+    /// Descends through the left of `||`/`??` chains to the first `&&`, so
+    /// `cond && <A /> || <B />` is guarded on `cond`.
+    fn leading_logical<'b>(
+        expression: &'b mut Expression<'a>,
+    ) -> Option<&'b mut LogicalExpression<'a>> {
+        match expression {
+            Expression::ParenthesizedExpression(parenthesized) => {
+                Self::leading_logical(&mut parenthesized.expression)
+            }
+            Expression::LogicalExpression(logical) => {
+                if logical.operator != LogicalOperator::And
+                    && matches!(
+                        logical.left.without_parentheses(),
+                        Expression::LogicalExpression(_)
+                    )
+                {
+                    Self::leading_logical(&mut logical.left)
+                } else {
+                    Some(logical)
                 }
             }
+            _ => None,
+        }
+    }
+
+    /// Replace a test with its memoized form
+    ///
+    /// Non-comparison tests are coerced with `!!` so the memo only changes when
+    /// truthiness does. Inline guards call `_$memo(() => test)` directly; others
+    /// call a new `_c$` that is returned with the test to declare.
+    fn memoize_test(
+        &mut self,
+        test: Expression<'a>,
+        inline: bool,
+    ) -> (Expression<'a>, Option<(String, Expression<'a>)>) {
+        let test = if matches!(test.without_parentheses(), Expression::BinaryExpression(_)) {
+            test
+        } else {
+            let not =
+                self.ast_builder
+                    .expression_unary(Span::default(), UnaryOperator::LogicalNot, test);
+            self.ast_builder
+                .expression_unary(Span::default(), UnaryOperator::LogicalNot, not)
+        };
+        let (callee, hoisted) = if inline {
+            self.register_import("memo");
+            let memo = self.create_call("_$memo", vec![self.create_arrow(&[], test)]);
+            (memo, None)
+        } else {
+            let id = self.generate_uid("c$");
+            (self.create_identifier(&id), Some((id, test)))
+        };
+        let guard = self.ast_builder.expression_call(
+            Span::default(),
+            callee,
+            None::<oxc_ast::ast::TSTypeParameterInstantiation>,
+            self.ast_builder.vec(),
+            false,
+        );
+        (guard, hoisted)
+    }
+
+    /// Record a runtime helper that the generated code depends on
+    ///
+    /// Returns the helper's local name. Renderer helpers come from the current
+    /// renderer's module; when two renderers provide the same helper, the later
+    /// one is numbered like Babel's uids (`_$insert2`).
+    pub(crate) fn register_import(&mut self, name: &str) -> String {
+        let module = if SHARED_HELPERS.contains(&name) {
+            &self.options.module_name
+        } else {
+            &self.renderer_module
+        };
+        if let Some(import) = self
+            .required_imports
+            .iter()
+            .find(|import| import.name == name && &import.module == module)
+        {
+            return import.local.clone();
         }
 
-        wrapper.push_str("  return _el$;\n");
-        wrapper.push_str("})()");
-        wrapper
+        let taken = self
+            .required_imports
+            .iter()
+            .filter(|import| import.name == name)
+            .count();
+        let local = if taken == 0 {
+            format!("_${}", name)
+        } else {
+            format!("_${}{}", name, taken + 1)
+        };
+        self.required_imports.push(RuntimeImport {
+            name: name.to_string(),
+            module: module.clone(),
+            local: local.clone(),
+        });
+        local
+    }
+
+    /// Create an identifier reference expression
+    pub(crate) fn create_identifier(&self, name: &str) -> Expression<'a> {
+        self.ast_builder
+            .expression_identifier(Span::default(), self.ast_builder.atom(name))
+    }
+
+    /// Create a dotted member access such as `_el$.firstChild.nextSibling`
+    pub(crate) fn create_member_path(&self, path: &str) -> Expression<'a> {
+        let mut parts = path.split('.');
+        let mut expr = self.create_identifier(parts.next().unwrap_or_default());
+        for property in parts {
+            expr = Expression::from(
+                self.ast_builder.member_expression_static(
+                    Span::default(),
+                    expr,
+                    self.ast_builder
+                        .identifier_name(Span::default(), self.ast_builder.atom(property)),
+                    false,
+                ),
+            );
+        }
+        expr
+    }
+
+    /// Create a call expression `callee(args...)`
+    pub(crate) fn create_call(
+        &self,
+        callee: &str,
+        arguments: Vec<Expression<'a>>,
+    ) -> Expression<'a> {
+        self.ast_builder.expression_call(
+            Span::default(),
+            self.create_identifier(callee),
+            None::<oxc_ast::ast::TSTypeParameterInstantiation>,
+            self.ast_builder
+                .vec_from_iter(arguments.into_iter().map(Argument::from)),
+            false,
+        )
+    }
+
+    /// Create an expression-bodied arrow function `(params) => body`
+    pub(crate) fn create_arrow(&self, params: &[&str], body: Expression<'a>) -> Expression<'a> {
+        let items = self.ast_builder.vec_from_iter(params.iter().map(|param| {
+            self.ast_builder.formal_parameter(
+                Span::default(),
+                self.ast_builder.vec(),
+                self.ast_builder.binding_pattern(
+                    self.ast_builder.binding_pattern_kind_binding_identifier(
+                        Span::default(),
+                        self.ast_builder.atom(param),
+                    ),
+                    None::<oxc_ast::ast::TSTypeAnnotation>,
+                    false,
+                ),
+                None,
+                false,
+                false,
+            )
+        }));
+        let params = self.ast_builder.formal_parameters(
+            Span::default(),
+            FormalParameterKind::ArrowFormalParameters,
+            items,
+            None::<oxc_ast::ast::BindingRestElement>,
+        );
+        let body = self.ast_builder.function_body(
+            Span::default(),
+            self.ast_builder.vec(),
+            self.ast_builder
+                .vec1(self.ast_builder.statement_expression(Span::default(), body)),
+        );
+        self.ast_builder.expression_arrow_function(
+            Span::default(),
+            true,
+            false,
+            None::<oxc_ast::ast::TSTypeParameterDeclaration>,
+            params,
+            None::<oxc_ast::ast::TSTypeAnnotation>,
+            body,
+        )
+    }
+
+    /// Create an arrow function with a block body `(params) => { statements }`
+    pub(crate) fn create_block_arrow(
+        &self,
+        params: &[&str],
+        statements: oxc_allocator::Vec<'a, Statement<'a>>,
+    ) -> Expression<'a> {
+        let Expression::ArrowFunctionExpression(mut arrow) =
+            self.create_arrow(params, self.create_identifier("undefined"))
+        else {
+            unreachable!()
+        };
+        arrow.expression = false;
+        arrow.body.statements = statements;
+        Expression::ArrowFunctionExpression(arrow)
+    }
+
+    /// Make a reactive spread lazy so `_$mergeProps` re-reads it
+    ///
+    /// A bare call like `{...getProps()}` is passed as `getProps`; anything else
+    /// is wrapped in `() => ...`.
+    pub(crate) fn create_dynamic_spread(&self, argument: Expression<'a>) -> Expression<'a> {
+        if let Expression::CallExpression(call) = &argument {
+            if call.arguments.is_empty()
+                && !matches!(
+                    call.callee,
+                    Expression::CallExpression(_)
+                        | Expression::StaticMemberExpression(_)
+                        | Expression::ComputedMemberExpression(_)
+                        | Expression::PrivateFieldExpression(_)
+                )
+            {
+                let Expression::CallExpression(mut call) = argument else {
+                    unreachable!()
+                };
+                return call.callee.take_in(self.allocator);
+            }
+        }
+        self.create_arrow(&[], argument)
+    }
+
+    /// Create `name: value`
+    pub(crate) fn create_property(
+        &self,
+        name: &str,
+        value: Expression<'a>,
+    ) -> ObjectPropertyKind<'a> {
+        self.ast_builder.object_property_kind_object_property(
+            Span::default(),
+            PropertyKind::Init,
+            self.create_property_key(name),
+            value,
+            false,
+            false,
+            false,
+        )
+    }
+
+    /// Create `get name() { body }`
+    pub(crate) fn create_getter(
+        &self,
+        name: &str,
+        body: oxc_allocator::Vec<'a, Statement<'a>>,
+    ) -> ObjectPropertyKind<'a> {
+        let ast = self.ast_builder;
+        let params = ast.formal_parameters(
+            Span::default(),
+            FormalParameterKind::UniqueFormalParameters,
+            ast.vec(),
+            None::<oxc_ast::ast::BindingRestElement>,
+        );
+        let body = ast.function_body(Span::default(), ast.vec(), body);
+        let getter = ast.expression_function(
+            Span::default(),
+            FunctionType::FunctionExpression,
+            None,
+            false,
+            false,
+            false,
+            None::<oxc_ast::ast::TSTypeParameterDeclaration>,
+            None::<oxc_ast::ast::TSThisParameter>,
+            params,
+            None::<oxc_ast::ast::TSTypeAnnotation>,
+            Some(body),
+        );
+        ast.object_property_kind_object_property(
+            Span::default(),
+            PropertyKind::Get,
+            self.create_property_key(name),
+            getter,
+            false,
+            false,
+            false,
+        )
+    }
+
+    /// Prop names that are not valid identifiers, like `hyphen-ated`, are quoted
+    pub(crate) fn create_property_key(&self, name: &str) -> PropertyKey<'a> {
+        let ast = self.ast_builder;
+        if is_identifier_name(name) {
+            ast.property_key_static_identifier(Span::default(), ast.atom(name))
+        } else {
+            PropertyKey::StringLiteral(ast.alloc_string_literal(
+                Span::default(),
+                ast.atom(name),
+                None,
+            ))
+        }
+    }
+
+    /// Create an immediately invoked arrow function `(() => { statements })()`
+    pub(crate) fn create_iife(
+        &self,
+        statements: oxc_allocator::Vec<'a, Statement<'a>>,
+    ) -> Expression<'a> {
+        self.ast_builder.expression_call(
+            Span::default(),
+            self.create_block_arrow(&[], statements),
+            None::<oxc_ast::ast::TSTypeParameterInstantiation>,
+            self.ast_builder.vec(),
+            false,
+        )
+    }
+
+    /// Create `target.path = value`
+    pub(crate) fn create_assignment(&self, target: &str, value: Expression<'a>) -> Expression<'a> {
+        let Expression::StaticMemberExpression(member) = self.create_member_path(target) else {
+            unreachable!("assignment targets are member paths")
+        };
+        self.ast_builder.expression_assignment(
+            Span::default(),
+            AssignmentOperator::Assign,
+            AssignmentTarget::StaticMemberExpression(member),
+            value,
+        )
+    }
+
+    /// Create the declarator `name = init`
+    pub(crate) fn create_declarator(
+        &self,
+        name: &str,
+        init: Expression<'a>,
+    ) -> VariableDeclarator<'a> {
+        let binding = self.ast_builder.binding_pattern(
+            self.ast_builder.binding_pattern_kind_binding_identifier(
+                Span::default(),
+                self.ast_builder.atom(name),
+            ),
+            None::<oxc_ast::ast::TSTypeAnnotation>,
+            false,
+        );
+        self.ast_builder.variable_declarator(
+            Span::default(),
+            VariableDeclarationKind::Var,
+            binding,
+            Some(init),
+            false,
+        )
+    }
+
+    /// Create `[end, content] = _$getNextMarker(start.nextSibling)`
+    fn create_marker_declarator(
+        &mut self,
+        start: &str,
+        end: &str,
+        content: &str,
+    ) -> VariableDeclarator<'a> {
+        self.register_import("getNextMarker");
+        let ast = self.ast_builder;
+        let elements = [end, content].map(|name| {
+            Some(ast.binding_pattern(
+                ast.binding_pattern_kind_binding_identifier(Span::default(), ast.atom(name)),
+                None::<oxc_ast::ast::TSTypeAnnotation>,
+                false,
+            ))
+        });
+        let binding = ast.binding_pattern(
+            ast.binding_pattern_kind_array_pattern(
+                Span::default(),
+                ast.vec_from_iter(elements),
+                None::<oxc_ast::ast::BindingRestElement>,
+            ),
+            None::<oxc_ast::ast::TSTypeAnnotation>,
+            false,
+        );
+        let next_sibling = self.create_member_path(&format!("{}.nextSibling", start));
+        let init = self.create_call("_$getNextMarker", vec![next_sibling]);
+        ast.variable_declarator(
+            Span::default(),
+            VariableDeclarationKind::Var,
+            binding,
+            Some(init),
+            false,
+        )
+    }
+
+    /// Create a template literal quasi holding `cooked`, escaped for the raw text
+    pub(crate) fn create_template_element(
+        &self,
+        cooked: &str,
+        tail: bool,
+    ) -> oxc_ast::ast::TemplateElement<'a> {
+        let raw = TemplateGenerator::escape_template_literal(cooked);
+        self.ast_builder.template_element(
+            Span::default(),
+            TemplateElementValue {
+                raw: self.ast_builder.atom(&raw),
+                cooked: Some(self.ast_builder.atom(cooked)),
+            },
+            tail,
+        )
+    }
+
+    /// Create `const alias = this;`
+    pub(crate) fn create_this_alias(&self, alias: &str) -> Statement<'a> {
+        let declarator = VariableDeclarator {
+            kind: VariableDeclarationKind::Const,
+            ..self.create_declarator(alias, self.ast_builder.expression_this(Span::default()))
+        };
+        Statement::VariableDeclaration(self.ast_builder.alloc(
+            self.ast_builder.variable_declaration(
+                Span::default(),
+                VariableDeclarationKind::Const,
+                self.ast_builder.vec1(declarator),
+                false,
+            ),
+        ))
+    }
+
+    /// Create `var a = ..., b = ...;`
+    pub(crate) fn create_var_declaration(
+        &self,
+        declarators: Vec<VariableDeclarator<'a>>,
+    ) -> Statement<'a> {
+        Statement::VariableDeclaration(self.ast_builder.alloc(
+            self.ast_builder.variable_declaration(
+                Span::default(),
+                VariableDeclarationKind::Var,
+                self.ast_builder.vec_from_iter(declarators),
+                false,
+            ),
+        ))
     }
 }
 
@@ -636,6 +2349,30 @@ impl<'a> JSXTransformer<'a> {
 pub enum TransformError {
     #[allow(dead_code)]
     NotImplemented(&'static str),
+    Component(ComponentError),
+    Event(EventError),
+}
+
+impl std::fmt::Display for TransformError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotImplemented(feature) => write!(f, "{feature} is not implemented"),
+            Self::Component(error) => write!(f, "{error}"),
+            Self::Event(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl From<ComponentError> for TransformError {
+    fn from(error: ComponentError) -> Self {
+        Self::Component(error)
+    }
+}
+
+impl From<EventError> for TransformError {
+    fn from(error: EventError) -> Self {
+        Self::Event(error)
+    }
 }
 
 #[cfg(test)]
@@ -645,7 +2382,8 @@ mod tests {
     #[test]
     fn test_jsx_transformer_creation() {
         let allocator = Allocator::default();
-        let transformer = JSXTransformer::new(&allocator);
+        let options = DomExpressionsTransformOptions::default();
+        let transformer = JSXTransformer::new(&options, &allocator);
         assert_eq!(transformer.template_counter, 0);
     }
 }
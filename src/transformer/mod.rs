@@ -1,16 +1,24 @@
-use oxc_allocator::Allocator;
+use oxc_allocator::{Allocator, CloneIn, TakeIn};
 use oxc_ast::ast::{
-    Argument, Expression, JSXElementName, Program, Span, Statement, VariableDeclarationKind,
+    Argument, ArrayExpressionElement, ArrowFunctionExpression, Expression, ImportOrExportKind,
+    Program, PropertyDefinition, Span, Statement, TemplateElementValue, VariableDeclarationKind,
 };
 use oxc_ast::AstBuilder;
+use oxc_ast_visit::{walk_mut, VisitMut};
+use oxc_semantic::SemanticBuilder;
+
+use crate::utils::template::TemplateGenerator;
 
 pub mod components;
 pub mod events;
 pub mod jsx;
 pub mod optimization;
+pub mod ssr;
+pub mod universal;
 
 // Re-export main types
-pub use jsx::JSXTransformer;
+pub use events::{EventTransformer, DELEGATED_EVENTS};
+pub use jsx::{JSXTransformer, RuntimeImport};
 
 #[derive(Debug, Clone)]
 pub enum ModuleFormat {
@@ -24,25 +32,101 @@ impl Default for ModuleFormat {
     }
 }
 
+/// How native elements are built at runtime, like Babel's `generate`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GenerateMode {
+    /// Clone HTML templates and walk to the dynamic nodes
+    #[default]
+    Dom,
+    /// Build elements one by one through a custom renderer's
+    /// `createElement`/`insertNode`/`setProp`
+    Universal,
+    /// Pick DOM or universal output per element from `renderers`, so custom
+    /// renderer trees and HTML islands can live in the same module
+    Dynamic,
+}
+
+/// A renderer that owns a set of native tags in `GenerateMode::Dynamic`, like
+/// an entry of Babel's `renderers`
+///
+/// Tags no renderer claims are built by the universal renderer at `module_name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Renderer {
+    /// `"dom"` compiles to HTML templates; any other name to universal calls
+    pub name: String,
+    /// Module the renderer's helpers are imported from
+    pub module_name: String,
+    /// Tags compiled with this renderer
+    pub elements: Vec<String>,
+}
+
+impl Renderer {
+    /// The DOM renderer, owning the standard HTML and SVG elements
+    pub fn dom(module_name: &str) -> Self {
+        Self {
+            name: "dom".to_string(),
+            module_name: module_name.to_string(),
+            elements: jsx::HTML_ELEMENTS
+                .iter()
+                .chain(jsx::SVG_ELEMENTS)
+                .map(|element| element.to_string())
+                .collect(),
+        }
+    }
+
+    /// Whether this renderer compiles to HTML templates
+    pub fn is_dom(&self) -> bool {
+        self.name == "dom"
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DomExpressionsTransformOptions {
+    /// Module the runtime helpers are imported from, like Babel's `moduleName`
+    pub module_name: String,
+    pub generate: GenerateMode,
+    /// Renderers `GenerateMode::Dynamic` chooses from by tag name
+    pub renderers: Vec<Renderer>,
+    /// Render native elements to strings with `_$ssr` instead of building DOM nodes
     pub generate_ssr: bool,
     pub hydratable: bool,
     pub delegation: bool,
+    /// Events compiled to delegated `$$name` handlers when `delegation` is on
+    ///
+    /// Defaults to dom-expressions' `DelegatedEvents`; add names to delegate
+    /// custom events or remove them to bind those events directly.
+    pub delegated_events: Vec<String>,
     pub context_to_custom_elements: bool,
     pub static_marker: String,
+    /// Re-apply reactive attributes inside `_$effect`, like Babel's `effectWrapper`
+    ///
+    /// When off, attributes are set once with their current value.
+    pub effect_wrapper: bool,
+    /// Memoize dynamic children with `_$memo`, like Babel's `memoWrapper`
+    ///
+    /// When off, dynamic children are passed as plain thunks.
     pub memo_wrapper: bool,
+    /// Guard conditional children on a memoized condition so their branches
+    /// only re-render when it flips, like Babel's `wrapConditionals`
     pub wrap_conditionals: bool,
 }
 
 impl Default for DomExpressionsTransformOptions {
     fn default() -> Self {
         Self {
+            module_name: "r-dom".to_string(),
+            generate: GenerateMode::Dom,
+            renderers: vec![Renderer::dom("r-dom")],
             generate_ssr: false,
             hydratable: false,
             delegation: true,
+            delegated_events: DELEGATED_EVENTS
+                .iter()
+                .map(|event| event.to_string())
+                .collect(),
             context_to_custom_elements: false,
             static_marker: "$$".to_string(),
+            effect_wrapper: true,
             memo_wrapper: true,
             wrap_conditionals: true,
         }
@@ -55,6 +139,9 @@ pub struct DomExpressionsTransform<'a> {
     template_counter: usize,
     allocator: &'a Allocator,
     ast_builder: AstBuilder<'a>,
+    jsx_transformer: JSXTransformer<'a>,
+    /// Elements that could not be compiled and were left as JSX
+    errors: Vec<String>,
 }
 
 impl<'a> DomExpressionsTransform<'a> {
@@ -64,215 +151,299 @@ impl<'a> DomExpressionsTransform<'a> {
             template_counter: 0,
             allocator,
             ast_builder: AstBuilder::new(allocator),
+            jsx_transformer: JSXTransformer::new(options, allocator),
+            errors: Vec::new(),
         }
     }
 
+    /// Why elements were left untransformed, in source order
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
     /// Main transformation entry point
     pub fn transform_program(&mut self, program: &mut Program<'a>) {
-        let mut jsx_transformer = JSXTransformer::new(self.allocator);
-
-        // Collect all JSX elements and generate templates
-        self.collect_jsx_templates(program, &mut jsx_transformer);
-
-        // Add import statement and template declarations if we have templates
-        if !jsx_transformer.get_templates().is_empty() {
-            self.add_import_statement(program, &jsx_transformer);
-            self.add_template_declarations(program, &jsx_transformer);
-        }
-
-        println!(
-            "Transformation completed with {} templates",
-            jsx_transformer.get_templates().len()
-        );
-    }
+        // Resolve bindings up front so event handlers and refs can be checked against them
+        let semantic = SemanticBuilder::new().build(program).semantic;
+        self.jsx_transformer.hoisted_handlers =
+            EventTransformer::collect_hoisted_handlers(&semantic);
+        self.jsx_transformer.constant_references =
+            JSXTransformer::collect_constant_references(&semantic);
 
-    /// Recursively find and process JSX elements to generate templates
-    fn collect_jsx_templates(
-        &mut self,
-        program: &mut Program<'a>,
-        jsx_transformer: &mut JSXTransformer,
-    ) {
-        for stmt in &mut program.body {
-            self.visit_statement(stmt, jsx_transformer);
-        }
-    }
+        // Find and transform JSX anywhere in the module, collecting templates
+        self.visit_program(program);
 
-    /// Process different statement types
-    fn visit_statement(&mut self, stmt: &mut Statement<'a>, jsx_transformer: &mut JSXTransformer) {
-        match stmt {
-            Statement::FunctionDeclaration(func) => {
-                if let Some(body) = &mut func.body {
-                    for stmt in &mut body.statements {
-                        self.visit_statement(stmt, jsx_transformer);
-                    }
-                }
-            }
-            Statement::ReturnStatement(ret) => {
-                if let Some(expr) = &mut ret.argument {
-                    self.visit_expression(expr, jsx_transformer);
-                }
-            }
-            Statement::VariableDeclaration(var_decl) => {
-                // Handle variable declarations like const template = <div>...</div>
-                for declarator in &mut var_decl.declarations {
-                    if let Some(init_expr) = &mut declarator.init {
-                        self.visit_expression(init_expr, jsx_transformer);
-                    }
-                }
-            }
-            Statement::ExpressionStatement(expr_stmt) => {
-                self.visit_expression(&mut expr_stmt.expression, jsx_transformer);
-            }
-            _ => {
-                // Handle other statement types as needed
-            }
+        // Register delegated events once for the whole module
+        if let Some(statement) =
+            EventTransformer::new().generate_delegate_events(&mut self.jsx_transformer)
+        {
+            program.body.push(statement);
         }
-    }
 
-    /// Process expressions to find JSX elements
-    fn visit_expression(
-        &mut self,
-        expr: &mut Expression<'a>,
-        jsx_transformer: &mut JSXTransformer,
-    ) {
-        match expr {
-            Expression::JSXElement(jsx_element) => {
-                // Transform JSX element and generate template
-                if let Ok(template_call_name) = jsx_transformer.transform_jsx_element(jsx_element) {
-                    println!("Generated template call for JSX: {}", template_call_name);
-
-                    // Replace JSX with actual CallExpression in AST
-                    let template_name = template_call_name.trim_end_matches("()");
-                    let call_expr = self.create_template_call_expression(template_name);
-                    *expr = call_expr;
-                }
-            }
-            Expression::ParenthesizedExpression(paren_expr) => {
-                // Handle parenthesized expressions like (<div>...</div>)
-                self.visit_expression(&mut paren_expr.expression, jsx_transformer);
-            }
-            Expression::AssignmentExpression(assign_expr) => {
-                self.visit_expression(&mut assign_expr.right, jsx_transformer);
-            }
-            Expression::CallExpression(_call_expr) => {
-                // Skip call expression arguments for now to avoid API issues
-                // TODO: Handle call expression arguments properly
-            }
-            _ => {
-                // Handle other expression types as needed
-            }
+        // Add template declarations if we have templates, then the runtime imports above them
+        if !self.jsx_transformer.get_templates().is_empty()
+            || !self.jsx_transformer.ssr_templates.is_empty()
+        {
+            self.add_template_declarations(program, &self.jsx_transformer);
         }
+        self.add_import_statement(program, &self.jsx_transformer);
     }
 
-    /// Create a template call expression: _tmpl$1()
-    fn create_template_call_expression(&self, template_name: &str) -> Expression<'a> {
-        let template_identifier = self
-            .ast_builder
-            .expression_identifier(Span::default(), self.ast_builder.atom(template_name));
-
-        self.ast_builder.expression_call(
-            Span::default(),
-            template_identifier,
-            None::<oxc_ast::ast::TSTypeParameterInstantiation>, // type_arguments
-            self.ast_builder.vec(),                             // empty arguments for now
-            false,                                              // optional_chain
-        )
-    }
-
-    /// Add template declarations to the beginning of the program
+    /// Add a single `var _tmpl$ = ..., _tmpl$2 = ...;` declaration after the module's imports
     fn add_template_declarations(
         &self,
         program: &mut Program<'a>,
         jsx_transformer: &JSXTransformer,
     ) {
+        let mut declarators = self.ast_builder.vec();
+
+        // Templates are declared in creation order
         for (template_name, template_html) in jsx_transformer.get_templates() {
-            // 1. Create string literal for now (will be changed to template literal later)
-            let template_string = self.ast_builder.expression_string_literal(
-                Span::default(),
-                self.ast_builder.atom(&template_html),
-                None,
-            );
+            // 1. Create template literal holding the escaped HTML
+            let template_string = self.create_template_literal(template_html);
 
-            // 2. Create template function call
+            // 2. Create /*#__PURE__*/ template function call
             let template_identifier = self
                 .ast_builder
                 .expression_identifier(Span::default(), self.ast_builder.atom("_$template"));
 
-            let template_call = self.ast_builder.expression_call(
+            // SVG templates add isImportNode, isSVG and isMathML flags
+            let mut arguments = self.ast_builder.vec1(Argument::from(template_string));
+            if jsx_transformer.svg_templates.contains(template_name) {
+                for flag in [false, true, false] {
+                    arguments.push(Argument::from(
+                        self.ast_builder
+                            .expression_boolean_literal(Span::default(), flag),
+                    ));
+                }
+            }
+
+            let template_call = self.ast_builder.expression_call_with_pure(
                 Span::default(),
                 template_identifier,
                 None::<oxc_ast::ast::TSTypeParameterInstantiation>, // type_arguments
-                self.ast_builder.vec1(Argument::from(template_string)), // arguments
+                arguments,                                          // arguments
                 false,                                              // optional_chain
+                true,                                               // pure
             );
 
-            // 3. Create binding identifier for variable name
-            let _binding_id = self
-                .ast_builder
-                .binding_identifier(Span::default(), self.ast_builder.atom(&template_name));
+            // 3. Create the `_tmpl$ = ...` declarator
+            declarators.push(self.create_template_declarator(template_name, template_call));
+        }
 
-            // 4. Create binding pattern
-            let binding_pattern = self.ast_builder.binding_pattern(
-                self.ast_builder.binding_pattern_kind_binding_identifier(
+        // Server templates are plain strings, or string arrays split around values
+        for (template_name, parts) in &jsx_transformer.ssr_templates {
+            let template = if parts.len() == 1 {
+                self.create_string_literal(&parts[0])
+            } else {
+                self.ast_builder.expression_array(
                     Span::default(),
-                    self.ast_builder.atom(&template_name),
-                ),
-                None::<oxc_ast::ast::TSTypeAnnotation>, // type_annotation
-                false,                                  // optional
-            );
+                    self.ast_builder.vec_from_iter(parts.iter().map(|part| {
+                        ArrayExpressionElement::from(self.create_string_literal(part))
+                    })),
+                )
+            };
+            declarators.push(self.create_template_declarator(template_name, template));
+        }
+
+        // 4. Create one variable declaration holding every template
+        let var_decl = self.ast_builder.variable_declaration(
+            Span::default(),
+            VariableDeclarationKind::Var, // Use 'var' instead of 'const' to match expected output
+            declarators,
+            false, // declare
+        );
+        let statement = Statement::VariableDeclaration(self.ast_builder.alloc(var_decl));
+
+        // 5. Insert after the leading import declarations
+        let insert_at = program
+            .body
+            .iter()
+            .take_while(|stmt| matches!(stmt, Statement::ImportDeclaration(_)))
+            .count();
+        program.body.insert(insert_at, statement);
+    }
 
-            // 5. Create variable declarator
-            let declarator = self.ast_builder.variable_declarator(
+    /// Create `name = init` for the template declaration
+    fn create_template_declarator(
+        &self,
+        template_name: &str,
+        init: Expression<'a>,
+    ) -> oxc_ast::ast::VariableDeclarator<'a> {
+        let binding_pattern = self.ast_builder.binding_pattern(
+            self.ast_builder.binding_pattern_kind_binding_identifier(
                 Span::default(),
-                VariableDeclarationKind::Var, // Use 'var' to match expected output
-                binding_pattern,
-                Some(template_call),
-                false, // definite
-            );
+                self.ast_builder.atom(template_name),
+            ),
+            None::<oxc_ast::ast::TSTypeAnnotation>, // type_annotation
+            false,                                  // optional
+        );
+        self.ast_builder.variable_declarator(
+            Span::default(),
+            VariableDeclarationKind::Var, // Use 'var' to match expected output
+            binding_pattern,
+            Some(init),
+            false, // definite
+        )
+    }
+
+    fn create_string_literal(&self, value: &str) -> Expression<'a> {
+        self.ast_builder.expression_string_literal(
+            Span::default(),
+            self.ast_builder.atom(value),
+            None,
+        )
+    }
+
+    /// Create a template literal with a single quasi: `` `<div>...</div>` ``
+    fn create_template_literal(&self, html: &str) -> Expression<'a> {
+        let raw = TemplateGenerator::escape_template_literal(html);
+        let quasi = self.ast_builder.template_element(
+            Span::default(),
+            TemplateElementValue {
+                raw: self.ast_builder.atom(&raw),
+                cooked: Some(self.ast_builder.atom(html)),
+            },
+            true,
+        );
+        self.ast_builder.expression_template_literal(
+            Span::default(),
+            self.ast_builder.vec1(quasi),
+            self.ast_builder.vec(),
+        )
+    }
+
+    /// Add import declarations for the runtime helpers used by the generated code
+    ///
+    /// Each helper gets its own `import { name as _$name } from "<module>";`.
+    fn add_import_statement(&self, program: &mut Program<'a>, jsx_transformer: &JSXTransformer) {
+        let imports = jsx_transformer
+            .get_required_imports()
+            .into_iter()
+            .map(|import| self.create_import_declaration(&import));
 
-            // 6. Create variable declaration
-            let var_decl = self.ast_builder.variable_declaration(
+        program.body.splice(0..0, imports);
+    }
+
+    /// Create `import { name as _$name } from "<module>";`
+    fn create_import_declaration(&self, import: &RuntimeImport) -> Statement<'a> {
+        let specifier = self
+            .ast_builder
+            .import_declaration_specifier_import_specifier(
                 Span::default(),
-                VariableDeclarationKind::Var, // Use 'var' instead of 'const' to match expected output
-                self.ast_builder.vec1(declarator),
-                false, // declare
+                self.ast_builder.module_export_name_identifier_name(
+                    Span::default(),
+                    self.ast_builder.atom(&import.name),
+                ),
+                self.ast_builder
+                    .binding_identifier(Span::default(), self.ast_builder.atom(&import.local)),
+                ImportOrExportKind::Value,
             );
+        let source = self.ast_builder.string_literal(
+            Span::default(),
+            self.ast_builder.atom(&import.module),
+            None,
+        );
+        let declaration = self.ast_builder.module_declaration_import_declaration(
+            Span::default(),
+            Some(self.ast_builder.vec1(specifier)),
+            source,
+            None,
+            None::<oxc_ast::ast::WithClause>,
+            ImportOrExportKind::Value,
+        );
 
-            // 7. Create statement
-            let statement = Statement::VariableDeclaration(self.ast_builder.alloc(var_decl));
+        Statement::from(declaration)
+    }
+}
 
-            // 8. Insert at beginning of program body
-            program.body.insert(0, statement);
+impl<'a> VisitMut<'a> for DomExpressionsTransform<'a> {
+    /// Declare the `_self$` aliases of each statement's components ahead of it
+    fn visit_statements(&mut self, statements: &mut oxc_allocator::Vec<'a, Statement<'a>>) {
+        let outer = std::mem::take(&mut self.jsx_transformer.this_aliases);
+        let mut index = 0;
+        while index < statements.len() {
+            self.visit_statement(&mut statements[index]);
+            let aliases = std::mem::take(&mut self.jsx_transformer.this_aliases);
+            for alias in &aliases {
+                statements.insert(index, self.jsx_transformer.create_this_alias(alias));
+                index += 1;
+            }
+            index += 1;
         }
+        self.jsx_transformer.this_aliases = outer;
     }
 
-    /// Generate unique template name
-    fn get_next_template_name(&mut self) -> String {
-        self.template_counter += 1;
-        format!("_tmpl${}", self.template_counter)
+    /// An expression body that gained `_self$` aliases becomes a block body
+    fn visit_arrow_function_expression(&mut self, it: &mut ArrowFunctionExpression<'a>) {
+        walk_mut::walk_arrow_function_expression(self, it);
+        if it.expression && it.body.statements.len() > 1 {
+            it.expression = false;
+            if let Some(statement) = it.body.statements.last_mut() {
+                if let Statement::ExpressionStatement(body) = statement {
+                    let value = body.expression.take_in(self.allocator);
+                    *statement = self
+                        .ast_builder
+                        .statement_return(Span::default(), Some(value));
+                }
+            }
+        }
     }
 
-    /// Add import statement for dom-expressions template function
-    fn add_import_statement(&self, program: &mut Program<'a>, jsx_transformer: &JSXTransformer) {
-        // Get required imports from JSX transformer
-        let required_imports = jsx_transformer.get_required_imports();
-
-        if !required_imports.is_empty() {
-            let imports_str = required_imports.join(", ");
-            println!(
-                "Import statement needed: import {{ {} }} from \"r-dom\";",
-                imports_str
+    /// Class fields have no statement to declare `_self$` before, so their
+    /// value is wrapped in an IIFE instead
+    fn visit_property_definition(&mut self, it: &mut PropertyDefinition<'a>) {
+        let outer = std::mem::take(&mut self.jsx_transformer.this_aliases);
+        walk_mut::walk_property_definition(self, it);
+        let aliases = std::mem::replace(&mut self.jsx_transformer.this_aliases, outer);
+        if let (false, Some(value)) = (aliases.is_empty(), &mut it.value) {
+            let mut statements = self.ast_builder.vec_from_iter(
+                aliases
+                    .iter()
+                    .map(|alias| self.jsx_transformer.create_this_alias(alias)),
+            );
+            statements.push(
+                self.ast_builder
+                    .statement_return(Span::default(), Some(value.take_in(self.allocator))),
             );
+            *value = self.jsx_transformer.create_iife(statements);
         }
-
-        // TODO: Actually add import to AST using OXC API
-        // For now, this will be handled in post-processing
     }
 
-    /// Check if JSX element name represents a dom-expressions component
-    fn is_dom_expressions_component(&self, _name: &JSXElementName) -> bool {
-        // TODO: Implement dom-expressions component detection
-        false
+    /// Replace JSX elements and fragments with their compiled form wherever they appear
+    ///
+    /// Elements are compiled before their contents are visited, so JSX nested in
+    /// attribute or child expressions is reached when the generated code is walked.
+    /// An element that fails to compile is put back exactly as it was written.
+    fn visit_expression(&mut self, expr: &mut Expression<'a>) {
+        let compiled = match expr {
+            Expression::JSXElement(jsx_element) => {
+                let original =
+                    Expression::JSXElement(jsx_element.clone_in_with_semantic_ids(self.allocator));
+                let checkpoint = self.jsx_transformer.checkpoint();
+                let result = self.jsx_transformer.transform_jsx_element(jsx_element);
+                Some((original, checkpoint, result))
+            }
+            Expression::JSXFragment(fragment) => {
+                let original =
+                    Expression::JSXFragment(fragment.clone_in_with_semantic_ids(self.allocator));
+                let checkpoint = self.jsx_transformer.checkpoint();
+                let result = self.jsx_transformer.transform_fragment(fragment);
+                Some((original, checkpoint, result))
+            }
+            _ => None,
+        };
+        if let Some((original, checkpoint, result)) = compiled {
+            match result {
+                Ok(transformed) => *expr = transformed,
+                Err(error) => {
+                    self.errors.push(error.to_string());
+                    self.jsx_transformer.rollback(checkpoint);
+                    *expr = original;
+                }
+            }
+        }
+        walk_mut::walk_expression(self, expr);
     }
 }
 
@@ -293,12 +464,12 @@ mod tests {
     fn test_template_name_generation() {
         let allocator = Allocator::default();
         let options = DomExpressionsTransformOptions::default();
-        let mut transform = DomExpressionsTransform::new(&options, &allocator);
+        let mut jsx_transformer = JSXTransformer::new(&options, &allocator);
 
-        let name1 = transform.get_next_template_name();
-        assert_eq!(name1, "_tmpl$1");
+        let name1 = jsx_transformer.get_next_template_name();
+        assert_eq!(name1, "_tmpl$");
 
-        let name2 = transform.get_next_template_name();
+        let name2 = jsx_transformer.get_next_template_name();
         assert_eq!(name2, "_tmpl$2");
     }
 
@@ -307,7 +478,7 @@ mod tests {
         let allocator = Allocator::default();
         let options = DomExpressionsTransformOptions::default();
         let transform = DomExpressionsTransform::new(&options, &allocator);
-        let jsx_transformer = JSXTransformer::new(&allocator);
+        let jsx_transformer = JSXTransformer::new(&options, &allocator);
 
         // Test that we can access templates without errors
         assert_eq!(jsx_transformer.get_templates().len(), 0);
@@ -319,9 +490,10 @@ mod tests {
     #[test]
     fn test_jsx_transformer_template_insertion() {
         let allocator = Allocator::default();
-        let mut jsx_transformer = JSXTransformer::new(&allocator);
+        let options = DomExpressionsTransformOptions::default();
+        let mut jsx_transformer = JSXTransformer::new(&options, &allocator);
 
-        // Add a mock template to verify the HashMap functionality
+        // Add a mock template to verify the IndexMap functionality
         jsx_transformer
             .templates
             .insert("_tmpl$1".to_string(), "<div>Hello</div>".to_string());
@@ -334,6 +506,807 @@ mod tests {
         );
     }
 
+    fn transform_source(source: &str, options: &DomExpressionsTransformOptions) -> String {
+        let allocator = Allocator::default();
+        let source_type = oxc_span::SourceType::default().with_jsx(true);
+        let mut program = oxc_parser::Parser::new(&allocator, source, source_type)
+            .parse()
+            .program;
+        DomExpressionsTransform::new(options, &allocator).transform_program(&mut program);
+        oxc_codegen::Codegen::new().build(&program).code
+    }
+
+    #[test]
+    fn test_dynamic_element_emits_iife() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source("const a = <div id={foo}>Hello {name}</div>;", &options);
+
+        assert!(output.contains("const a = (() => {"));
+        assert!(output.contains("var _el$ = _tmpl$(), _el$2 = _el$.firstChild;"));
+        assert!(output.contains("_$insert(_el$, name, null);"));
+        assert!(output.contains("_$setAttribute(_el$, \"id\", foo);"));
+        assert!(output.contains("return _el$;"));
+    }
+
+    #[test]
+    fn test_dynamic_expressions_are_preserved() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            "const a = <div title={props.title}>Count: {count()}</div>; const b = <p>Total {a + b}</p>;",
+            &options,
+        );
+
+        assert!(output.contains("_$insert(_el$, count, null);"));
+        assert!(output.contains("_$insert(_el$3, a + b, null);"));
+        assert!(output.contains("_$effect(() => _$setAttribute(_el$, \"title\", props.title));"));
+    }
+
+    #[test]
+    fn test_jsx_is_transformed_in_nested_positions() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            render(() => <main />, root);
+            export default () => <div>{cond() ? <span>yes</span> : <b>no</b>}</div>;
+            class View { render() { return <p />; } }
+            const props = { icon: <i />, list() { return [<u />]; } };
+            "#,
+            &options,
+        );
+
+        assert!(!output.contains("/>"), "{output}");
+        assert!(output.contains("render(() => _tmpl$(), root);"));
+        assert!(output.contains("export default () =>"));
+        assert!(output.contains("_c$() ? _tmpl$3() : _tmpl$4()"));
+        assert!(output.contains("return _tmpl$5();"));
+        assert!(output.contains("icon: _tmpl$6()"));
+        assert!(output.contains("return [_tmpl$7()];"));
+    }
+
+    #[test]
+    fn test_nested_dynamic_nodes_are_walked() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"const a = <div id="main"><h1 class="base" title={welcoming()}><a href={"/"} data-id={id}>Welcome</a></h1><p>static</p><b>{count()}</b></div>;"#,
+            &options,
+        );
+
+        assert!(output.contains(
+            "var _el$ = _tmpl$(), _el$2 = _el$.firstChild, _el$3 = _el$2.firstChild, _el$4 = _el$2.nextSibling, _el$5 = _el$4.nextSibling;"
+        ), "{output}");
+        assert!(output.contains("_$setAttribute(_el$3, \"data-id\", id);"));
+        assert!(output.contains("_$insert(_el$5, count);"));
+        assert!(output.contains("_$effect(() => _$setAttribute(_el$2, \"title\", welcoming()));"));
+        assert!(output.contains(
+            "_$template(`<div id=main><h1 class=base><a href=/>Welcome</a></h1><p>static</p><b></b></div>`)"
+        ));
+    }
+
+    #[test]
+    fn test_inserts_between_text_use_markers() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            "const a = <span> {greeting} {name} </span>; const b = <span> {greeting}{name} </span>;",
+            &options,
+        );
+
+        assert!(
+            output.contains("_$template(`<span> <!> <!> </span>`)"),
+            "{output}"
+        );
+        assert!(output.contains(
+            "var _el$ = _tmpl$(), _el$2 = _el$.firstChild, _el$5 = _el$2.nextSibling, _el$3 = _el$5.nextSibling, _el$6 = _el$3.nextSibling, _el$4 = _el$6.nextSibling;"
+        ));
+        assert!(output.contains("_$insert(_el$, greeting, _el$5);"));
+        assert!(output.contains("_$insert(_el$, name, _el$6);"));
+
+        assert!(output.contains("_$template(`<span> <!> </span>`)"));
+        assert!(output.contains("_$insert(_el$7, greeting, _el$10);"));
+        assert!(output.contains("_$insert(_el$7, name, _el$10);"));
+    }
+
+    #[test]
+    fn test_multiple_dynamic_attributes_share_one_effect() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            "const a = <div title={title()} style={{ color: color() }} />;",
+            &options,
+        );
+
+        assert!(
+            output.contains("var _v$ = title(), _v$2 = { color: color() };"),
+            "{output}"
+        );
+        assert!(output.contains("_v$ !== _p$.e && _$setAttribute(_el$, \"title\", _p$.e = _v$);"));
+        assert!(output.contains("_p$.t = _$style(_el$, _v$2, _p$.t);"));
+        assert!(output.contains("return _p$;"));
+    }
+
+    #[test]
+    fn test_components_compile_to_create_component() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            const a = <Child name="Jake" dynamic={state.data} handler={onClick} hyphen-ated={state.data} flag />;
+            const b = <Context.Consumer>{context => context}</Context.Consumer>;
+            const c = <For each={list()} fallback={<Loading />}>{item => item}</For>;
+            "#,
+            &options,
+        );
+
+        assert!(
+            output.contains("const a = _$createComponent(Child, {"),
+            "{output}"
+        );
+        assert!(output.contains("name: \"Jake\","));
+        assert!(output.contains("get dynamic() {\n\t\treturn state.data;\n\t}"));
+        assert!(output.contains("handler: onClick,"));
+        assert!(output.contains("get \"hyphen-ated\"() {"));
+        assert!(output.contains("flag: true"));
+        assert!(output.contains(
+            "const b = _$createComponent(Context.Consumer, { children: (context) => context });"
+        ));
+        assert!(output.contains("get fallback() {\n\t\treturn _$createComponent(Loading, {});"));
+        assert!(output.contains("children: (item) => item"));
+        assert!(
+            output.starts_with("import { createComponent as _$createComponent } from \"r-dom\";")
+        );
+    }
+
+    #[test]
+    fn test_component_spreads_use_merge_props() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            const a = <Child name="John" {...props} flag title={title()} {...rest} />;
+            const b = <Child {...dynamicSpread()} />;
+            const c = <Child {...state.props} name="x" />;
+            const d = <Child {...props} />;
+            "#,
+            &options,
+        );
+
+        assert!(
+            output.contains(
+                "const a = _$createComponent(Child, _$mergeProps({ name: \"John\" }, props, {"
+            ),
+            "{output}"
+        );
+        assert!(output.contains(
+            "flag: true,\n\tget title() {\n\t\treturn title();\n\t}
+}, rest));"
+        ));
+        assert!(output.contains("const b = _$createComponent(Child, _$mergeProps(dynamicSpread));"));
+        assert!(output.contains(
+            "const c = _$createComponent(Child, _$mergeProps(() => state.props, { name: \"x\" }));"
+        ));
+        assert!(output.contains("const d = _$createComponent(Child, props);"));
+    }
+
+    #[test]
+    fn test_component_children() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            const a = <div><Link>new</Link>{" | "}<Link>comments</Link></div>;
+            const b = <Child><div>{content}</div></Child>;
+            const c = <Child><div />{state.dynamic}After</Child>;
+            "#,
+            &options,
+        );
+
+        assert!(output.contains("_$template(`<div> | </div>`)"), "{output}");
+        assert!(output
+            .contains("_$insert(_el$, _$createComponent(Link, { children: \"new\" }), _el$2);"));
+        assert!(output.contains(
+            "get children() {\n\tvar _el$3 = _tmpl$2();\n\t_$insert(_el$3, content);\n\treturn _el$3;\n}"
+        ));
+        assert!(output.contains("_$memo(() => state.dynamic),"));
+        assert!(output.contains("\"After\""));
+    }
+
+    #[test]
+    fn test_conditional_expressions() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            const a = <div>{state.count > 5 ? (state.dynamic ? best : good()) : bad}</div>;
+            const b = <div>{state.dynamic && state.something && good()}</div>;
+            const c = <div>{(state.dynamic && good()) || bad}</div>;
+            const d = <div>{simple ? good() : bad}</div>;
+            const e = <div>{thing() || thing1()}</div>;
+            const f = <Comp render={state.dynamic ? good() : bad} />;
+            const g = <Comp render={state.dynamic ? good : bad} />;
+            "#,
+            &options,
+        );
+
+        assert!(output.contains(
+            "var _c$ = _$memo(() => state.count > 5);\n\t\treturn () => _c$() ? _$memo(() => !!state.dynamic)() ? best : good() : bad;"
+        ));
+        assert!(output.contains(
+            "var _c$2 = _$memo(() => !!(state.dynamic && state.something));\n\t\treturn () => _c$2() && good();"
+        ));
+        assert!(output.contains("return () => _c$3() && good() || bad;"));
+        assert!(output.contains("_$insert(_el$4, () => simple ? good() : bad);"));
+        assert!(output.contains("_$insert(_el$5, () => thing() || thing1());"));
+        assert!(output.contains("return _$memo(() => !!state.dynamic)() ? good() : bad;"));
+        assert!(output.contains("return state.dynamic ? good : bad;"));
+    }
+
+    #[test]
+    fn test_fragments() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            const a = <><div>First</div>{inserted}<div>Last</div>After</>;
+            const b = <>{inserted}</>;
+            const c = <>{inserted()}</>;
+            const d = <><div />{state.inserted}<Component /></>;
+            const e = <>{state.dynamic ? <div>Output</div> : <></>}</>;
+            const f = <div><>{a}</></div>;
+            "#,
+            &options,
+        );
+
+        assert!(!output.contains("<>"), "{output}");
+        assert!(
+            output.contains("const a = [\n\t_tmpl$(),\n\tinserted,\n\t_tmpl$2(),\n\t\"After\"\n];")
+        );
+        assert!(output.contains("const b = inserted;"));
+        assert!(output.contains("const c = _$memo(inserted);"));
+        assert!(output.contains(
+            "const d = [\n\t_tmpl$3(),\n\t_$memo(() => state.inserted),\n\t_$createComponent(Component, {})\n];"
+        ));
+        assert!(output
+            .contains("const e = _$memo(() => _$memo(() => !!state.dynamic)() ? _tmpl$4() : []);"));
+        assert!(output.contains("_$insert(_el$5, a);"));
+    }
+
+    #[test]
+    fn test_wrapperless() {
+        let options = DomExpressionsTransformOptions {
+            effect_wrapper: false,
+            memo_wrapper: false,
+            wrap_conditionals: false,
+            ..Default::default()
+        };
+        let output = transform_source(
+            r#"
+            const a = <div id={state.first} innerHTML={state?.dynamic ? "a" : "b"}>{state.name}</div>;
+            const b = <Child><div />{state.dynamic}</Child>;
+            const c = <div>{state.dynamic ? good() : bad}</div>;
+            "#,
+            &options,
+        );
+
+        assert!(!output.contains("_$effect"));
+        assert!(!output.contains("_$memo"));
+        assert!(output.contains("_$setAttribute(_el$, \"id\", state.first);"));
+        assert!(output.contains("_el$.innerHTML = state?.dynamic ? \"a\" : \"b\";"));
+        assert!(output.contains("_$insert(_el$, () => state.name);"));
+        assert!(output.contains("return [_tmpl$2(), () => state.dynamic];"));
+        assert!(output.contains("_$insert(_el$3, () => state.dynamic ? good() : bad);"));
+    }
+
+    #[test]
+    fn test_component_this_alias() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            class Template {
+                render() {
+                    return <Comp value={this.x} onClick={() => this.y}>{this.label}</Comp>;
+                }
+                field = <this.component method={this.method} />;
+                arrow = () => <Comp value={this.z} />;
+            }
+            "#,
+            &options,
+        );
+
+        assert!(output.contains("const _self$ = this;\n\t\treturn _$createComponent(Comp"));
+        assert!(output.contains("return _self$.x;"));
+        assert!(output.contains("onClick: () => _self$.y"));
+        assert!(output.contains("return _self$.label;"));
+        assert!(output.contains(
+            "field = (() => {\n\t\tconst _self$2 = this;\n\t\treturn _$createComponent(this.component"
+        ));
+        assert!(output.contains("arrow = () => {\n\t\tconst _self$3 = this;"));
+    }
+
+    #[test]
+    fn test_component_getters_keep_user_iifes() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            const a = <Comp value={((val = 123) => { return val; })()} />;
+            const b = <Comp value={(async () => { await load(); })()} />;
+            const c = <Comp><div title={title()} /></Comp>;
+            const d = <Comp when={(() => { const foo = test(); return foo; })()} />;
+            const e = <Comp when={(() => prop.red ? "red" : "green")()} />;
+            "#,
+            &options,
+        );
+
+        assert!(output.contains("return ((val = 123) => {"), "{output}");
+        assert!(output.contains("return (async () => {"), "{output}");
+        assert!(
+            output.contains("get children() {\n\tvar _el$ = _tmpl$();"),
+            "{output}"
+        );
+        assert!(
+            output.contains("get when() {\n\tconst foo = test();\n\treturn foo;\n}"),
+            "{output}"
+        );
+        assert!(
+            output.contains("get when() {\n\treturn prop.red ? \"red\" : \"green\";\n}"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn test_string_attributes_decode_entities() {
+        let source = r#"const a = <input normal="Search&hellip;" title="a &amp; b" />;"#;
+
+        let output = transform_source(source, &DomExpressionsTransformOptions::default());
+        assert!(
+            output.contains("_$template(`<input normal=Search… title=\"a &amp; b\">`)"),
+            "{output}"
+        );
+
+        let ssr = DomExpressionsTransformOptions {
+            generate_ssr: true,
+            ..Default::default()
+        };
+        let output = transform_source(source, &ssr);
+        assert!(
+            output.contains(r#"normal=\"Search…\" title=\"a &amp; b\""#),
+            "{output}"
+        );
+
+        let universal = DomExpressionsTransformOptions {
+            generate: GenerateMode::Universal,
+            ..Default::default()
+        };
+        let output = transform_source(source, &universal);
+        assert!(
+            output.contains("_$setProp(_el$, \"normal\", \"Search…\");"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn test_native_element_refs() {
+        let source = r#"
+            const ref = null;
+            let target;
+            const a = <div ref={ref} />;
+            const b = <div ref={target} />;
+        "#;
+
+        let output = transform_source(source, &DomExpressionsTransformOptions::default());
+        assert!(output.contains("_$use(ref, _el$);"), "{output}");
+        assert!(
+            output
+                .contains("typeof _ref$ === \"function\" ? _$use(_ref$, _el$2) : target = _el$2;"),
+            "{output}"
+        );
+        assert!(!output.contains("\"ref\""), "{output}");
+    }
+
+    #[test]
+    fn test_merged_classes() {
+        let source = r#"
+            const a = <div class="a" className="b" />;
+            const b = <div class="a" className={b} />;
+        "#;
+
+        let output = transform_source(source, &DomExpressionsTransformOptions::default());
+        assert!(output.contains("<div class=\"a b\"></div>"), "{output}");
+        assert!(
+            output.contains("_$className(_el$2, `a ${b || \"\"}`);"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn test_component_text_decodes_entities() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source("const a = <Button>&times; &rarr;</Button>;", &options);
+
+        assert!(output.contains("children: \"× →\""), "{output}");
+    }
+
+    #[test]
+    fn test_invalid_event_handlers_are_reported() {
+        let allocator = Allocator::default();
+        let options = DomExpressionsTransformOptions::default();
+        let source = "const a = <div><button onClick={[]} /></div>;\nconst b = <div onClick={} />;\nconst c = <Comp><button onClick={[]} /></Comp>;\nconst d = <><button onClick={[]} />text</>;";
+        let mut program = oxc_parser::Parser::new(&allocator, source, oxc_span::SourceType::jsx())
+            .parse()
+            .program;
+        let mut transform = DomExpressionsTransform::new(&options, &allocator);
+        transform.transform_program(&mut program);
+
+        assert_eq!(
+            transform.errors(),
+            [
+                "invalid event handler: missing bound handler",
+                "invalid event handler: missing bound handler",
+                "invalid event handler: missing bound handler"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_universal_children_are_reported() {
+        let allocator = Allocator::default();
+        let options = DomExpressionsTransformOptions {
+            generate: GenerateMode::Dynamic,
+            ..Default::default()
+        };
+        let source = "const a = <mesh><button onClick={[]} /></mesh>;\nconst b = <mesh><Comp><button onClick={[]} /></Comp></mesh>;";
+        let mut program = oxc_parser::Parser::new(&allocator, source, oxc_span::SourceType::jsx())
+            .parse()
+            .program;
+        let mut transform = DomExpressionsTransform::new(&options, &allocator);
+        transform.transform_program(&mut program);
+        let output = oxc_codegen::Codegen::new().build(&program).code;
+
+        assert_eq!(
+            transform.errors(),
+            [
+                "invalid event handler: missing bound handler",
+                "invalid event handler: missing bound handler"
+            ]
+        );
+        assert!(
+            output.contains("const a = <mesh><button onClick={[]} /></mesh>;"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn test_failed_elements_are_left_intact() {
+        let allocator = Allocator::default();
+        let options = DomExpressionsTransformOptions::default();
+        let source =
+            "const a = <div title={x()}><button onClick={[]} /></div>;\nconst b = <p>{y()}</p>;";
+        let mut program = oxc_parser::Parser::new(&allocator, source, oxc_span::SourceType::jsx())
+            .parse()
+            .program;
+        let mut transform = DomExpressionsTransform::new(&options, &allocator);
+        transform.transform_program(&mut program);
+        let output = oxc_codegen::Codegen::new().build(&program).code;
+
+        assert_eq!(
+            transform.errors(),
+            ["invalid event handler: missing bound handler"]
+        );
+        assert!(
+            output.contains("const a = <div title={x()}><button onClick={[]} /></div>;"),
+            "{output}"
+        );
+        assert!(
+            output.contains("var _tmpl$ = /* @__PURE__ */ _$template(`<p></p>`);"),
+            "{output}"
+        );
+        assert!(!output.contains("setAttribute"), "{output}");
+    }
+
+    #[test]
+    fn test_ssr_escapes_unary_results() {
+        let options = DomExpressionsTransformOptions {
+            generate_ssr: true,
+            ..Default::default()
+        };
+        let output = transform_source(
+            "const a = <div>{typeof a} {!b} {-c} {~d} {delete e.f}</div>;",
+            &options,
+        );
+
+        assert!(
+            output.contains("typeof a, !b, -c, _$escape(~d), _$escape(delete e.f))"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn test_svg_templates() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            const a = <svg width="400"><rect x="50" /></svg>;
+            const b = <rect x="50" />;
+            const c = <svg><a xlink:href={url}><text>Docs</text></a></svg>;
+            "#,
+            &options,
+        );
+
+        assert!(output.contains("_$template(`<svg width=400><rect x=50></rect></svg>`)"));
+        assert!(
+            output.contains("_$template(`<svg><rect x=50></rect></svg>`, false, true, false)"),
+            "{output}"
+        );
+        assert!(
+            output.contains(
+                "_$setAttributeNS(_el$4, \"http://www.w3.org/1999/xlink\", \"xlink:href\", url);"
+            ),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn test_native_spreads_use_spread() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            const a = <div id="main" {...results} classList={{ selected: unknown }} foo>Hi</div>;
+            const b = <div start="Hi" middle={middle} {...spread}>Hi</div>;
+            const c = <input {...getProps("test")} disabled />;
+            "#,
+            &options,
+        );
+
+        assert!(
+            output.contains("_$template(`<div id=main>Hi</div>`)"),
+            "{output}"
+        );
+        assert!(output.contains(
+            "_$spread(_el$, _$mergeProps(results, {\n\t\tclassList: { selected: unknown },\n\t\tfoo: \"\"\n\t}), false, true);"
+        ));
+        assert!(output.contains(
+            "_$setAttribute(_el$2, \"middle\", middle);\n\t_$spread(_el$2, spread, false, true);"
+        ));
+        assert!(output.contains(
+            "_$spread(_el$3, _$mergeProps(() => getProps(\"test\"), { disabled: true }), false, false);"
+        ));
+        assert!(output.contains("import { spread as _$spread } from \"r-dom\";"));
+    }
+
+    #[test]
+    fn test_delegated_events() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            const a = <div><button onClick={() => console.log("delegated")}>A</button><input onInput={handler} onclick="x()" /></div>;
+            const b = <button onclick={go} title={title}>B</button>;
+            "#,
+            &options,
+        );
+
+        assert!(
+            output.contains("_$template(`<div><button>A</button><input onclick=x()></div>`)"),
+            "{output}"
+        );
+        assert!(output.contains("_el$2.$$click = () => console.log(\"delegated\");"));
+        assert!(output.contains("_el$3.$$input = handler;"));
+        assert!(output.contains("_el$4.$$click = go;\n\t_$setAttribute(_el$4, \"title\", title);"));
+        assert!(output.ends_with("_$delegateEvents([\"click\", \"input\"]);\n"));
+        assert!(output.contains("import { delegateEvents as _$delegateEvents } from \"r-dom\";"));
+
+        let options = DomExpressionsTransformOptions {
+            delegation: false,
+            ..Default::default()
+        };
+        let output = transform_source(
+            "const go = () => {};\nconst a = <button onClick={go}>B</button>;",
+            &options,
+        );
+        assert!(
+            output.contains("_el$.addEventListener(\"click\", go);"),
+            "{output}"
+        );
+        assert!(!output.contains("_$delegateEvents"));
+    }
+
+    #[test]
+    fn test_bound_event_handlers() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            const a = <div>
+              <button onClick={[id => console.log("delegated", id), rowId]}>A</button>
+              <button onScroll={[id => console.log("bound", id), id]}>B</button>
+              <button onClick={[handler]}>C</button>
+            </div>;
+            "#,
+            &options,
+        );
+
+        assert!(output.contains(
+            "_el$2.$$click = (id) => console.log(\"delegated\", id);\n\t_el$2.$$clickData = rowId;"
+        ), "{output}");
+        assert!(output.contains(
+            "_$addEventListener(_el$3, \"scroll\", [(id) => console.log(\"bound\", id), id]);"
+        ));
+        assert!(output.contains("_el$4.$$click = handler;"));
+        assert!(!output.contains("$$clickData = undefined"));
+        assert!(
+            output.contains("import { addEventListener as _$addEventListener } from \"r-dom\";")
+        );
+    }
+
+    #[test]
+    fn test_namespaced_event_listeners() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            function hoistedCustomEvent1() {}
+            const a = <button
+              on:CAPS-ev={() => console.log("custom")}
+              on:inlined-with-options={{handleEvent:()=> console.log("listener"), once:false}}
+              on:inlined-to-hoisted1={{handleEvent:hoistedCustomEvent1}}
+              on:with-this={{handleEvent: function() { this.count++; }, count: 0}}
+              oncapture:camelClick={() => console.log("listener")}
+            >Click</button>;
+            "#,
+            &options,
+        );
+
+        assert!(
+            output.contains("_$template(`<button>Click</button>`)"),
+            "{output}"
+        );
+        assert!(
+            output.contains("_el$.addEventListener(\"CAPS-ev\", () => console.log(\"custom\"));")
+        );
+        assert!(output.contains(
+            "_el$.addEventListener(\"inlined-with-options\", () => console.log(\"listener\"), { once: false });"
+        ));
+        assert!(
+            output.contains("_el$.addEventListener(\"inlined-to-hoisted1\", hoistedCustomEvent1);")
+        );
+        assert!(
+            output
+                .contains("_el$.addEventListener(\"with-this\", {\n\t\thandleEvent: function() {"),
+            "{output}"
+        );
+        assert!(output.contains(
+            "_el$.addEventListener(\"camelClick\", () => console.log(\"listener\"), true);"
+        ));
+        assert!(!output.contains("_$delegateEvents"));
+    }
+
+    #[test]
+    fn test_configurable_delegated_events() {
+        let source = "function a() {}
+function b() {}
+function c() {}
+function d() {}
+const el = <div onChange={a} onDblClick={b} onMyEvent={c} onClick={d} />;";
+
+        let output = transform_source(source, &DomExpressionsTransformOptions::default());
+        assert!(
+            output.contains("_el$.addEventListener(\"change\", a);"),
+            "{output}"
+        );
+        assert!(output.contains("_el$.$$dblclick = b;"));
+        assert!(output.contains("_el$.addEventListener(\"myevent\", c);"));
+        assert!(output.contains("_$delegateEvents([\"dblclick\", \"click\"]);"));
+
+        let mut options = DomExpressionsTransformOptions::default();
+        options.delegated_events.push("myevent".to_string());
+        options.delegated_events.retain(|event| event != "click");
+        let output = transform_source(source, &options);
+        assert!(output.contains("_el$.$$myevent = c;"), "{output}");
+        assert!(output.contains("_el$.addEventListener(\"click\", d);"));
+        assert!(output.contains("_$delegateEvents([\"dblclick\", \"myevent\"]);"));
+    }
+
+    #[test]
+    fn test_hoisted_event_handlers() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            function hoisted1() { console.log("hoisted"); }
+            const hoisted2 = () => console.log("hoisted");
+            const alias = hoisted2;
+            let reassigned = () => {};
+            reassigned = other;
+            const a = <div
+              onChange={hoisted1}
+              onScroll={alias}
+              onBlur={reassigned}
+              onFocus={handler}
+              on:custom={props.onCustom}
+              onWheel={() => console.log("inline")}
+            />;
+            "#,
+            &options,
+        );
+
+        assert!(
+            output.contains("_el$.addEventListener(\"change\", hoisted1);"),
+            "{output}"
+        );
+        assert!(output.contains("_el$.addEventListener(\"scroll\", alias);"));
+        assert!(output.contains("_el$.addEventListener(\"blur\", (e) => reassigned(e));"));
+        assert!(output.contains("_el$.addEventListener(\"focus\", (e) => handler(e));"));
+        assert!(output.contains("_el$.addEventListener(\"custom\", (e) => props.onCustom(e));"));
+        assert!(output.contains("_el$.addEventListener(\"wheel\", () => console.log(\"inline\"));"));
+    }
+
+    #[test]
+    fn test_runtime_imports_use_module_name() {
+        let options = DomExpressionsTransformOptions {
+            module_name: "r-custom".to_string(),
+            ..Default::default()
+        };
+        let output = transform_source("const a = <div title={props.title}>{name}</div>;", &options);
+
+        let imports: Vec<&str> = output.lines().take(4).collect();
+        assert_eq!(
+            imports,
+            vec![
+                "import { template as _$template } from \"r-custom\";",
+                "import { setAttribute as _$setAttribute } from \"r-custom\";",
+                "import { effect as _$effect } from \"r-custom\";",
+                "import { insert as _$insert } from \"r-custom\";",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_template_declaration_is_pure_template_literal() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"const a = <div title='say "hi"' class="`x`">{"${a}"}</div>;"#,
+            &options,
+        );
+
+        assert!(output.contains(
+            "var _tmpl$ = /* @__PURE__ */ _$template(`<div title=\"say &quot;hi&quot;\" class=\"\\`x\\`\">$\\{a}</div>`);"
+        ), "{output}");
+    }
+
+    #[test]
+    fn test_templates_share_one_ordered_declaration() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            r#"
+            import { helper } from "somewhere";
+            const a = <a />;
+            const b = <b />;
+            const c = <i />;
+            "#,
+            &options,
+        );
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            "import { template as _$template } from \"r-dom\";"
+        );
+        assert_eq!(lines[1], "import { helper } from \"somewhere\";");
+        assert_eq!(
+            lines[2],
+            "var _tmpl$ = /* @__PURE__ */ _$template(`<a></a>`), _tmpl$2 = /* @__PURE__ */ _$template(`<b></b>`), _tmpl$3 = /* @__PURE__ */ _$template(`<i></i>`);"
+        );
+    }
+
+    #[test]
+    fn test_parent_templates_are_declared_before_nested_ones() {
+        let options = DomExpressionsTransformOptions::default();
+        let output = transform_source(
+            "const a = <div><Comp><span>{x}</span></Comp><p /></div>;",
+            &options,
+        );
+
+        assert!(
+            output.contains(
+                "var _tmpl$ = /* @__PURE__ */ _$template(`<div><p></p></div>`), _tmpl$2 = /* @__PURE__ */ _$template(`<span></span>`);"
+            ),
+            "{output}"
+        );
+        assert!(
+            output.contains("var _el$ = _tmpl$(), _el$3 = _el$.firstChild;"),
+            "{output}"
+        );
+        assert!(output.contains("var _el$2 = _tmpl$2();"), "{output}");
+    }
+
     #[test]
     fn test_ast_builder_functionality() {
         let allocator = Allocator::default();
@@ -350,4 +1323,161 @@ mod tests {
         // If we get here without compilation errors, the OXC integration is working
         assert!(true);
     }
+
+    #[test]
+    fn test_ssr_templates() {
+        let options = DomExpressionsTransformOptions {
+            module_name: "r-server".to_string(),
+            generate_ssr: true,
+            ..Default::default()
+        };
+        let output = transform_source(
+            r#"const a = <div id="main" class={state.class} style={{ color: c() }}><h1 disabled={d}>Hi {name}</h1></div>;
+const b = <span>Static</span>;"#,
+            &options,
+        );
+
+        assert!(output.contains("import { ssr as _$ssr } from \"r-server\";"));
+        assert!(output.contains(
+            "var _tmpl$ = [\n\t\"<div id=\\\"main\\\"\",\n\t\" style=\\\"\",\n\t\"\\\"><h1\",\n\t\">Hi \",\n\t\"</h1></div>\"\n]"
+        ));
+        assert!(output.contains("_tmpl$2 = \"<span>Static</span>\""));
+        assert!(output.contains("_$ssrAttribute(\"class\", _$escape(state.class, true), false)"));
+        assert!(output.contains("_$ssrStyle({ color: c() })"));
+        assert!(output.contains("_$ssrAttribute(\"disabled\", d, true)"));
+        assert!(output.contains("_$escape(name)"));
+        assert!(output.contains("const b = _$ssr(_tmpl$2);"));
+    }
+
+    #[test]
+    fn test_hydratable_markers() {
+        let options = DomExpressionsTransformOptions {
+            hydratable: true,
+            ..Default::default()
+        };
+        let output = transform_source(
+            r#"const a = <span>{greeting} John</span>;
+const b = <div><button onClick={() => go()}>Hi</button></div>;
+const c = <div>Static</div>;"#,
+            &options,
+        );
+
+        assert!(output.contains("_$template(`<span><!$><!/> John</span>`)"));
+        assert!(output.contains(
+            "var _el$ = _$getNextElement(_tmpl$), _el$3 = _el$.firstChild, [_el$4, _co$] = _$getNextMarker(_el$3.nextSibling), _el$2 = _el$4.nextSibling;"
+        ));
+        assert!(output.contains("_$insert(_el$, greeting, _el$4, _co$);"));
+        assert!(output.contains("\t_$runHydrationEvents();\n\treturn _el$5;"));
+        assert!(output.contains("const c = _$getNextElement(_tmpl$3);"));
+    }
+
+    #[test]
+    fn test_hydratable_ssr() {
+        let options = DomExpressionsTransformOptions {
+            module_name: "r-server".to_string(),
+            generate_ssr: true,
+            hydratable: true,
+            ..Default::default()
+        };
+        let output = transform_source(
+            r#"const a = <html><head><title>Hi</title><Assets /></head><body><App /><footer>Bottom</footer></body></html>;
+const b = <div $ServerOnly><h1>Hello</h1>{state.interpolation}</div>;
+const c = <span id="x">{name}</span>;"#,
+            &options,
+        );
+
+        assert!(output.contains("var _tmpl$ = [\"<head><title>Hi</title>\", \"</head>\"]"));
+        assert!(output.contains(
+            "_tmpl$2 = [\n\t\"<html\",\n\t\">\",\n\t\"<body><!--$-->\",\n\t\"<!--/--><footer>Bottom</footer></body></html>\"\n]"
+        ));
+        assert!(output.contains(
+            "const a = _$ssr(_tmpl$2, _$ssrHydrationKey(), _$createComponent(_$NoHydration, { get children() {"
+        ));
+        assert!(output.contains(
+            "_tmpl$3 = [\n\t\"<div\",\n\t\"><h1>Hello</h1><!--$-->\",\n\t\"<!--/--></div>\"\n]"
+        ));
+        assert!(output.contains("const c = _$ssr(_tmpl$4, _$ssrHydrationKey(), _$escape(name));"));
+    }
+
+    #[test]
+    fn test_hydratable_ssr_document() {
+        let options = DomExpressionsTransformOptions {
+            module_name: "r-server".to_string(),
+            generate_ssr: true,
+            hydratable: true,
+            ..Default::default()
+        };
+        let output = transform_source(
+            include_str!("../../tests/__ssr_hydratable_fixtures__/document/code.js"),
+            &options,
+        );
+
+        assert!(output.contains(
+            "_tmpl$3 = [\"<head><title>🔥 Blazing 🔥</title><meta charset=\\\"UTF-8\\\"><meta name=\\\"viewport\\\" content=\\\"width=device-width, initial-scale=1.0\\\"><link rel=\\\"stylesheet\\\" href=\\\"/styles.css\\\"><!--$-->\", \"<!--/--></head>\"]"
+        ), "{output}");
+        assert!(
+            output.contains("return _$ssr(_tmpl$3, _$escape(_$createComponent(Assets, {})));"),
+            "{output}"
+        );
+        assert!(output.contains(
+            "return _$ssr(_tmpl$, `var data = [${d}]`, _$escape(_$createComponent(Assets, {})));"
+        ), "{output}");
+    }
+
+    #[test]
+    fn test_universal_elements() {
+        let options = DomExpressionsTransformOptions {
+            module_name: "r-custom".to_string(),
+            generate: GenerateMode::Universal,
+            ..Default::default()
+        };
+        let output = transform_source(
+            r#"const a = <div id="main" class={style()}><h1 title={title()}>Welcome</h1><p>Hi {name} !</p><Child /></div>;
+const b = <span />;"#,
+            &options,
+        );
+
+        assert!(output.contains("import { createElement as _$createElement } from \"r-custom\";"));
+        assert!(output.contains(
+            "var _el$ = _$createElement(\"div\"), _el$2 = _$createElement(\"h1\"), _el$4 = _$createElement(\"p\"), _el$5 = _$createTextNode(`Hi `), _el$6 = _$createTextNode(` !`);"
+        ));
+        assert!(output.contains("_$insertNode(_el$, _el$2);"));
+        assert!(output.contains(
+            "\t_$insertNode(_el$4, _el$6);\n\t_$insert(_el$4, name, _el$6);\n\t_$insert(_el$, _$createComponent(Child, {}), null);"
+        ));
+        assert!(output.contains("_$setProp(_el$, \"id\", \"main\");"));
+        assert!(output.contains("_$insertNode(_el$2, _$createTextNode(`Welcome`));"));
+        assert!(
+            output.contains("_v$ !== _p$.e && (_p$.e = _$setProp(_el$, \"class\", _v$, _p$.e));")
+        );
+        assert!(output.contains("const b = _$createElement(\"span\");"));
+    }
+
+    #[test]
+    fn test_dynamic_renderers() {
+        let options = DomExpressionsTransformOptions {
+            module_name: "r-custom".to_string(),
+            generate: GenerateMode::Dynamic,
+            ..Default::default()
+        };
+        let output = transform_source(
+            r#"const a = <div><div ref={set}>{props.children}</div><mesh scale={2} /></div>;
+const b = <group {...props}><HTML><div {...props}>{b}</div></HTML></group>;"#,
+            &options,
+        );
+
+        assert!(output.contains("import { template as _$template } from \"r-dom\";"));
+        assert!(output.contains("import { insert as _$insert } from \"r-dom\";"));
+        assert!(output.contains("import { insert as _$insert2 } from \"r-custom\";"));
+        assert!(output.contains("import { spread as _$spread2 } from \"r-dom\";"));
+        assert!(
+            output.contains("import { createComponent as _$createComponent } from \"r-custom\";")
+        );
+        assert!(output.contains("var _el$3 = _$createElement(\"mesh\");"));
+        assert!(output.contains("_$insert(_el$, (() => {"));
+        assert!(output.contains(
+            "\t_$spread(_el$4, props, true);\n\t_$insert2(_el$4, _$createComponent(HTML, { get children() {"
+        ));
+        assert!(output.contains("_$spread2(_el$5, props, false, true);"));
+    }
 }
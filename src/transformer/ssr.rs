@@ -0,0 +1,1025 @@
+/// Server-side rendering transformation for dom-expressions
+///
+/// With `generate_ssr` native elements render to strings instead of DOM nodes:
+/// - static HTML goes into string (or string array) templates
+/// - `_$ssr(_tmpl$, ...values)` interleaves the escaped dynamic values
+/// - attributes render through `_$ssrAttribute`, `_$ssrStyle` and `_$ssrClassList`
+/// - elements with spreads fall back to `_$ssrElement(tag, props, children)`
+use crate::transformer::components::ComponentTransformer;
+use crate::transformer::events::EventTransformer;
+use crate::transformer::jsx::{
+    JSXTransformer, TransformError, BOOLEAN_ATTRIBUTES, CHILD_PROPERTIES, VOID_ELEMENTS,
+};
+use crate::utils::ast_utils::AstUtils;
+use crate::utils::template::TemplateGenerator;
+use oxc_allocator::TakeIn;
+use oxc_ast::ast::{
+    ArrayExpressionElement, BinaryOperator, Expression, JSXAttribute, JSXAttributeItem,
+    JSXAttributeName, JSXAttributeValue, JSXChild, JSXElement, LogicalOperator, ObjectPropertyKind,
+    PropertyKind, Span, UnaryOperator,
+};
+
+/// A server template: static HTML split around the values rendered into it
+///
+/// `template` always holds one more string than `values`.
+pub struct SSRTemplate<'a> {
+    pub template: Vec<String>,
+    pub values: Vec<Expression<'a>>,
+    /// Whether the last value is an `_$ssrAttribute` that the next one can join
+    joins_attribute: bool,
+    /// Whether inserted values are wrapped in `<!--$-->`/`<!--/-->` hydration markers
+    pub hydratable: bool,
+}
+
+impl<'a> SSRTemplate<'a> {
+    pub fn new() -> Self {
+        Self {
+            template: vec![String::new()],
+            values: Vec::new(),
+            joins_attribute: false,
+            hydratable: false,
+        }
+    }
+
+    /// Create a template for content the client hydrates
+    pub fn hydratable() -> Self {
+        Self {
+            hydratable: true,
+            ..Self::new()
+        }
+    }
+
+    /// Append static HTML
+    pub fn push_static(&mut self, html: &str) {
+        if html.is_empty() {
+            return;
+        }
+        self.joins_attribute = false;
+        if let Some(last) = self.template.last_mut() {
+            last.push_str(html);
+        }
+    }
+
+    /// Render a value at the current position
+    pub fn push_value(&mut self, value: Expression<'a>) {
+        self.joins_attribute = false;
+        self.values.push(value);
+        self.template.push(String::new());
+    }
+}
+
+impl Default for SSRTemplate<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One piece of an element's merged `class`
+enum ClassPart<'a> {
+    Static(String),
+    Dynamic(Expression<'a>),
+}
+
+/// Attributes that render as element content rather than as attributes
+struct ChildContent<'a> {
+    name: String,
+    value: Expression<'a>,
+}
+
+pub struct SSRTransformer;
+
+impl SSRTransformer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Transform a native element into `_$ssr(_tmpl$, ...values)`
+    pub fn transform_element<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &mut JSXElement<'a>,
+    ) -> Result<Expression<'a>, TransformError> {
+        if Self::has_spread(element) {
+            return self.create_element(jsx, element);
+        }
+        let hydratable = jsx.options.hydratable;
+        if hydratable && AstUtils::get_element_name(element).as_deref() == Some("head") {
+            return self.create_no_hydration(jsx, element, true);
+        }
+        let mut template = if hydratable {
+            SSRTemplate::hydratable()
+        } else {
+            SSRTemplate::new()
+        };
+        self.transform_native_element(jsx, element, hydratable, &mut template)?;
+        Ok(self.create_template(jsx, template))
+    }
+
+    /// Render `<head>` outside hydration: `_$createComponent(_$NoHydration, { get children() {...} })`
+    ///
+    /// The client never claims head content, so it gets no hydration key. Only
+    /// a root `<head>` keeps `markers` around its inserts, as dom-expressions
+    /// does; inside `<html>` the whole head is a single insert.
+    fn create_no_hydration<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &mut JSXElement<'a>,
+        markers: bool,
+    ) -> Result<Expression<'a>, TransformError> {
+        let mut template = if markers {
+            SSRTemplate::hydratable()
+        } else {
+            SSRTemplate::new()
+        };
+        self.transform_native_element(jsx, element, false, &mut template)?;
+        let head = self.create_template(jsx, template);
+
+        jsx.register_import("NoHydration");
+        jsx.register_import("createComponent");
+        let body = jsx.ast_builder.vec1(
+            jsx.ast_builder
+                .statement_return(Span::default(), Some(head)),
+        );
+        let props = jsx.ast_builder.vec1(jsx.create_getter("children", body));
+        let props = jsx.ast_builder.expression_object(Span::default(), props);
+        let component = jsx.create_identifier("_$NoHydration");
+        Ok(jsx.create_call("_$createComponent", vec![component, props]))
+    }
+
+    fn has_spread(element: &JSXElement) -> bool {
+        element
+            .opening_element
+            .attributes
+            .iter()
+            .any(|attr| matches!(attr, JSXAttributeItem::SpreadAttribute(_)))
+    }
+
+    /// Render `<tag attributes>children</tag>` into `template`
+    ///
+    /// Root elements of hydratable output carry an `_$ssrHydrationKey()` so the
+    /// client can find them again.
+    fn transform_native_element<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &mut JSXElement<'a>,
+        hydration_key: bool,
+        template: &mut SSRTemplate<'a>,
+    ) -> Result<(), TransformError> {
+        let tag_name = AstUtils::get_element_name(element).unwrap_or_default();
+        // Raw text elements render their content unescaped
+        let escape = !matches!(tag_name.as_str(), "script" | "style");
+
+        template.push_static(&format!("<{tag_name}"));
+        if hydration_key {
+            jsx.register_import("ssrHydrationKey");
+            template.push_value(jsx.create_call("_$ssrHydrationKey", Vec::new()));
+        }
+        let child_content = self.transform_attributes(jsx, element, template);
+        template.push_static(">");
+        if VOID_ELEMENTS.contains(&tag_name.as_str()) {
+            return Ok(());
+        }
+
+        match child_content {
+            Some(content) => self.push_child_content(jsx, content, escape, template),
+            None => self.transform_children(jsx, &mut element.children, escape, template)?,
+        }
+        template.push_static(&format!("</{tag_name}>"));
+        Ok(())
+    }
+
+    /// Render the attributes of an element without spreads
+    ///
+    /// Returns an `innerHTML`/`textContent`-style attribute that replaces the
+    /// element's children.
+    fn transform_attributes<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &mut JSXElement<'a>,
+        template: &mut SSRTemplate<'a>,
+    ) -> Option<ChildContent<'a>> {
+        let attributes = &mut element.opening_element.attributes;
+
+        // `class`, `className`, `classList` and `class:*` merge into one attribute,
+        // rendered where the first of them appears; `style` works the same way
+        let mut class_parts = Vec::new();
+        let mut first_class = None;
+        let mut style_parts = Vec::new();
+        let mut first_style = None;
+        let mut child_content = None;
+
+        for (index, attr) in attributes.iter_mut().enumerate() {
+            let JSXAttributeItem::Attribute(attr) = attr else {
+                continue;
+            };
+            let name = Self::attribute_name(attr);
+            if name == "class"
+                || name == "className"
+                || name == "classList"
+                || name.starts_with("class:")
+            {
+                first_class.get_or_insert(index);
+                self.collect_class(jsx, &name, attr, &mut class_parts);
+            } else if name == "style" || name.starts_with("style:") {
+                first_style.get_or_insert(index);
+                if let Some(part) = self.take_value(jsx, attr) {
+                    style_parts.push((name.strip_prefix("style:").map(str::to_string), part));
+                }
+            } else if CHILD_PROPERTIES.contains(&name.as_str()) {
+                if let Some(value) = self.take_value(jsx, attr) {
+                    child_content = Some(ChildContent { name, value });
+                }
+            }
+        }
+
+        for (index, attr) in attributes.iter_mut().enumerate() {
+            if first_class == Some(index) {
+                self.push_class(jsx, std::mem::take(&mut class_parts), template);
+            }
+            if first_style == Some(index) {
+                self.push_style(jsx, std::mem::take(&mut style_parts), template);
+            }
+            let JSXAttributeItem::Attribute(attr) = attr else {
+                continue;
+            };
+            let name = Self::attribute_name(attr);
+            let skipped = name == "ref"
+                || name == "$ServerOnly"
+                || name.starts_with("use:")
+                || name.starts_with("prop:")
+                || EventTransformer::is_event_attribute(&name);
+            if skipped
+                || name == "class"
+                || name == "className"
+                || name == "classList"
+                || name.starts_with("class:")
+                || name == "style"
+                || name.starts_with("style:")
+                || CHILD_PROPERTIES.contains(&name.as_str())
+            {
+                continue;
+            }
+            let name = match name.as_str() {
+                "htmlFor" => "for",
+                name => name.strip_prefix("attr:").unwrap_or(name),
+            }
+            .to_string();
+            self.transform_attribute(jsx, &name, attr, template);
+        }
+        child_content
+    }
+
+    /// Render a single attribute into the template
+    fn transform_attribute<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        name: &str,
+        attr: &mut JSXAttribute<'a>,
+        template: &mut SSRTemplate<'a>,
+    ) {
+        let is_boolean = BOOLEAN_ATTRIBUTES.contains(&name);
+        let value = match &mut attr.value {
+            None => {
+                template.push_static(&format!(" {name}"));
+                return;
+            }
+            Some(JSXAttributeValue::StringLiteral(lit)) => {
+                let value = TemplateGenerator::decode_html_entities(&lit.value);
+                self.push_static_attribute(name, &value, template);
+                return;
+            }
+            Some(JSXAttributeValue::ExpressionContainer(expr_container)) => {
+                match jsx.take_expression(expr_container) {
+                    Some(expression) => expression,
+                    None => return,
+                }
+            }
+            Some(_) => return,
+        };
+
+        if let Expression::BooleanLiteral(lit) = &value {
+            if !is_boolean {
+                self.push_static_attribute(name, &lit.value.to_string(), template);
+            } else if lit.value {
+                template.push_static(&format!(" {name}"));
+            }
+            return;
+        }
+        if let Some(static_value) = AstUtils::get_static_value(&value) {
+            self.push_static_attribute(name, &static_value, template);
+            return;
+        }
+
+        let value = if is_boolean {
+            value
+        } else {
+            self.escape_expression(jsx, value, true)
+        };
+        let attribute = self.create_ssr_attribute(jsx, name, value, is_boolean);
+        self.push_attribute_value(jsx, attribute, template);
+    }
+
+    /// Append ` name="value"`, or just ` name` for an empty value
+    fn push_static_attribute(&self, name: &str, value: &str, template: &mut SSRTemplate) {
+        if value.is_empty() {
+            template.push_static(&format!(" {name}"));
+        } else {
+            let value = TemplateGenerator::escape_html(value, true);
+            template.push_static(&format!(" {name}=\"{value}\""));
+        }
+    }
+
+    /// Push an `_$ssrAttribute` call, joining it with an adjacent one by `+`
+    fn push_attribute_value<'a>(
+        &self,
+        jsx: &JSXTransformer<'a>,
+        attribute: Expression<'a>,
+        template: &mut SSRTemplate<'a>,
+    ) {
+        if template.joins_attribute {
+            if let Some(previous) = template.values.pop() {
+                let joined = jsx.ast_builder.expression_binary(
+                    Span::default(),
+                    previous,
+                    BinaryOperator::Addition,
+                    attribute,
+                );
+                template.values.push(joined);
+                return;
+            }
+        }
+        template.push_value(attribute);
+        template.joins_attribute = true;
+    }
+
+    /// Create `_$ssrAttribute("name", value, isBoolean)`
+    fn create_ssr_attribute<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        name: &str,
+        value: Expression<'a>,
+        is_boolean: bool,
+    ) -> Expression<'a> {
+        jsx.register_import("ssrAttribute");
+        let name = jsx.ast_builder.expression_string_literal(
+            Span::default(),
+            jsx.ast_builder.atom(name),
+            None,
+        );
+        let is_boolean = jsx
+            .ast_builder
+            .expression_boolean_literal(Span::default(), is_boolean);
+        jsx.create_call("_$ssrAttribute", vec![name, value, is_boolean])
+    }
+
+    /// Collect the classes contributed by a class-like attribute
+    fn collect_class<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        name: &str,
+        attr: &mut JSXAttribute<'a>,
+        parts: &mut Vec<ClassPart<'a>>,
+    ) {
+        let Some(value) = self.take_value(jsx, attr) else {
+            return;
+        };
+        if let Some(class_name) = name.strip_prefix("class:") {
+            self.push_toggled_class(jsx, class_name, value, parts);
+            return;
+        }
+        if name != "classList" {
+            match AstUtils::get_static_value(&value) {
+                Some(class) => parts.push(ClassPart::Static(TemplateGenerator::trim_whitespace(
+                    &class,
+                ))),
+                None => parts.push(ClassPart::Dynamic(self.escape_expression(jsx, value, true))),
+            }
+            return;
+        }
+
+        let Expression::ObjectExpression(mut object) = value else {
+            jsx.register_import("ssrClassList");
+            parts.push(ClassPart::Dynamic(
+                jsx.create_call("_$ssrClassList", vec![value]),
+            ));
+            return;
+        };
+        let static_keys = object.properties.iter().all(|property| {
+            matches!(
+                property,
+                ObjectPropertyKind::ObjectProperty(property)
+                    if property.kind == PropertyKind::Init
+                        && !property.computed
+                        && property.key.static_name().is_some()
+            )
+        });
+        if !static_keys {
+            jsx.register_import("ssrClassList");
+            let value = Expression::ObjectExpression(object);
+            parts.push(ClassPart::Dynamic(
+                jsx.create_call("_$ssrClassList", vec![value]),
+            ));
+            return;
+        }
+        for property in object.properties.iter_mut() {
+            let ObjectPropertyKind::ObjectProperty(property) = property else {
+                continue;
+            };
+            let class_name = property.key.static_name().unwrap_or_default().to_string();
+            let value = property.value.take_in(jsx.allocator);
+            self.push_toggled_class(jsx, &class_name, value, parts);
+        }
+    }
+
+    /// Add `class_name` when `condition` holds: statically or as `${cond ? "name" : ""}`
+    fn push_toggled_class<'a>(
+        &self,
+        jsx: &JSXTransformer<'a>,
+        class_name: &str,
+        condition: Expression<'a>,
+        parts: &mut Vec<ClassPart<'a>>,
+    ) {
+        match condition {
+            Expression::BooleanLiteral(lit) => {
+                if lit.value {
+                    parts.push(ClassPart::Static(class_name.to_string()));
+                }
+            }
+            condition => {
+                let class_name = jsx.ast_builder.expression_string_literal(
+                    Span::default(),
+                    jsx.ast_builder.atom(class_name),
+                    None,
+                );
+                let empty = jsx
+                    .ast_builder
+                    .expression_string_literal(Span::default(), "", None);
+                parts.push(ClassPart::Dynamic(jsx.ast_builder.expression_conditional(
+                    Span::default(),
+                    condition,
+                    class_name,
+                    empty,
+                )));
+            }
+        }
+    }
+
+    /// Render the merged `class` attribute
+    fn push_class<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        mut parts: Vec<ClassPart<'a>>,
+        template: &mut SSRTemplate<'a>,
+    ) {
+        if parts
+            .iter()
+            .all(|part| matches!(part, ClassPart::Static(_)))
+        {
+            let class = parts
+                .iter()
+                .filter_map(|part| match part {
+                    ClassPart::Static(class) if !class.is_empty() => Some(class.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !class.is_empty() {
+                self.push_static_attribute("class", &class, template);
+            }
+            return;
+        }
+        if parts.len() == 1 {
+            if let Some(ClassPart::Dynamic(value)) = parts.pop() {
+                let attribute = self.create_ssr_attribute(jsx, "class", value, false);
+                self.push_attribute_value(jsx, attribute, template);
+            }
+            return;
+        }
+
+        // `hi ${_$escape(state.class, true) || ""} ${cond ? "name" : ""}`
+        let mut quasis = jsx.ast_builder.vec();
+        let mut expressions = jsx.ast_builder.vec();
+        let mut quasi = String::new();
+        for (index, part) in parts.into_iter().enumerate() {
+            if index > 0 {
+                quasi.push(' ');
+            }
+            match part {
+                ClassPart::Static(class) => {
+                    quasi.push_str(&TemplateGenerator::escape_html(&class, true))
+                }
+                ClassPart::Dynamic(value) => {
+                    quasis.push(jsx.create_template_element(&quasi, false));
+                    quasi.clear();
+                    let value = match value {
+                        value @ Expression::ConditionalExpression(_) => value,
+                        value => {
+                            let empty = jsx.ast_builder.expression_string_literal(
+                                Span::default(),
+                                "",
+                                None,
+                            );
+                            jsx.ast_builder.expression_logical(
+                                Span::default(),
+                                value,
+                                LogicalOperator::Or,
+                                empty,
+                            )
+                        }
+                    };
+                    expressions.push(value);
+                }
+            }
+        }
+        quasis.push(jsx.create_template_element(&quasi, true));
+        let class =
+            jsx.ast_builder
+                .expression_template_literal(Span::default(), quasis, expressions);
+
+        template.push_static(" class=\"");
+        template.push_value(class);
+        template.push_static("\"");
+    }
+
+    /// Render the merged `style` attribute
+    ///
+    /// Static strings are normalized into the template; anything else renders
+    /// through `_$ssrStyle`, with `style:name` values merged into the object.
+    fn push_style<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        parts: Vec<(Option<String>, Expression<'a>)>,
+        template: &mut SSRTemplate<'a>,
+    ) {
+        let mut static_style = String::new();
+        let mut style = None;
+        let mut properties = Vec::new();
+        for (property, value) in parts {
+            match (property, AstUtils::get_static_value(&value)) {
+                (None, Some(css)) => static_style.push_str(&Self::normalize_style(&css)),
+                (None, None) => style = Some(value),
+                (Some(property), _) => properties.push((property, value)),
+            }
+        }
+
+        if style.is_none() && properties.is_empty() {
+            if !static_style.is_empty() {
+                self.push_static_attribute("style", &static_style, template);
+            }
+            return;
+        }
+
+        let style = match style {
+            Some(Expression::ObjectExpression(mut object)) => {
+                for (property, value) in properties {
+                    object
+                        .properties
+                        .push(jsx.create_property(&property, value));
+                }
+                Expression::ObjectExpression(object)
+            }
+            Some(style) if properties.is_empty() => style,
+            style => {
+                let mut object = jsx.ast_builder.vec();
+                if let Some(style) = style {
+                    object.push(ObjectPropertyKind::SpreadProperty(
+                        jsx.ast_builder.alloc_spread_element(Span::default(), style),
+                    ));
+                }
+                for (property, value) in properties {
+                    object.push(jsx.create_property(&property, value));
+                }
+                jsx.ast_builder.expression_object(Span::default(), object)
+            }
+        };
+        jsx.register_import("ssrStyle");
+        let style = jsx.create_call("_$ssrStyle", vec![style]);
+        template.push_static(" style=\"");
+        template.push_value(style);
+        template.push_static("\"");
+    }
+
+    /// Collapse a static style string to `name:value;` pairs
+    fn normalize_style(css: &str) -> String {
+        css.split(';')
+            .filter_map(|declaration| {
+                let (name, value) = declaration.split_once(':')?;
+                Some(format!("{}:{};", name.trim(), value.trim()))
+            })
+            .collect()
+    }
+
+    /// Render `innerHTML`, `textContent`, `innerText` or `children` as content
+    fn push_child_content<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        content: ChildContent<'a>,
+        escape: bool,
+        template: &mut SSRTemplate<'a>,
+    ) {
+        let raw = content.name == "innerHTML" || !escape;
+        match AstUtils::get_static_value(&content.value) {
+            Some(html) if raw => template.push_static(&html),
+            Some(text) => template.push_static(&TemplateGenerator::escape_html(&text, false)),
+            None if raw => template.push_value(content.value),
+            None => {
+                let value = self.escape_expression(jsx, content.value, false);
+                template.push_value(value);
+            }
+        }
+    }
+
+    /// Render element children into the template
+    fn transform_children<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        children: &mut oxc_allocator::Vec<'a, JSXChild<'a>>,
+        escape: bool,
+        template: &mut SSRTemplate<'a>,
+    ) -> Result<(), TransformError> {
+        // Hydrated inserts among siblings are delimited so the client can claim them
+        let markers = template.hydratable && {
+            let significant: Vec<&JSXChild<'a>> = children
+                .iter()
+                .filter(|child| JSXTransformer::is_significant_child(child))
+                .collect();
+            JSXTransformer::check_length(&significant)
+        };
+        for child in children.iter_mut() {
+            if !JSXTransformer::is_significant_child(child) {
+                continue;
+            }
+            match child {
+                JSXChild::Text(text) => {
+                    let raw = text.raw.unwrap_or(text.value);
+                    template.push_static(&TemplateGenerator::trim_whitespace(&raw));
+                }
+                JSXChild::Element(element) => {
+                    let tag_name = AstUtils::get_element_name(element).unwrap_or_default();
+                    if AstUtils::is_component(&tag_name) {
+                        let component =
+                            ComponentTransformer::new().transform_custom_component(jsx, element)?;
+                        let component = self.escape_expression(jsx, component, false);
+                        Self::push_insert(component, markers, template);
+                    } else if Self::has_spread(element) {
+                        let element = self.create_element(jsx, element)?;
+                        Self::push_insert(element, markers, template);
+                    } else if template.hydratable && tag_name == "head" {
+                        let head = self.create_no_hydration(jsx, element, false)?;
+                        template.push_value(head);
+                    } else {
+                        self.transform_native_element(jsx, element, false, template)?;
+                    }
+                }
+                JSXChild::ExpressionContainer(expr_container) => {
+                    let static_value = expr_container
+                        .expression
+                        .as_expression()
+                        .and_then(AstUtils::get_static_value);
+                    if let Some(text) = static_value {
+                        let text = if escape {
+                            TemplateGenerator::escape_html(&text, false)
+                        } else {
+                            text
+                        };
+                        template.push_static(&text);
+                        continue;
+                    }
+                    let Some(expression) = jsx.take_expression(expr_container) else {
+                        continue;
+                    };
+                    let expression = if escape {
+                        self.escape_expression(jsx, expression, false)
+                    } else {
+                        expression
+                    };
+                    Self::push_insert(expression, markers, template);
+                }
+                JSXChild::Fragment(fragment) => {
+                    let fragment = Expression::JSXFragment(fragment.take_in_box(jsx.allocator));
+                    Self::push_insert(fragment, markers, template);
+                }
+                JSXChild::Spread(spread) => {
+                    let expression = spread.expression.take_in(jsx.allocator);
+                    let expression = self.escape_expression(jsx, expression, false);
+                    Self::push_insert(expression, markers, template);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Push an inserted child value, between hydration markers when `markers` is set
+    fn push_insert<'a>(value: Expression<'a>, markers: bool, template: &mut SSRTemplate<'a>) {
+        if markers {
+            template.push_static("<!--$-->");
+        }
+        template.push_value(value);
+        if markers {
+            template.push_static("<!--/-->");
+        }
+    }
+
+    /// Escape a rendered value with `_$escape`, skipping parts that cannot hold markup
+    ///
+    /// Mirrors dom-expressions' `escapeExpression`: literals and the results of
+    /// `typeof`, `void`, `!`, `-` and `+` pass through, conditionals and logical expressions escape their branches,
+    /// and native JSX is rendered by the server runtime already.
+    pub fn escape_expression<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        mut expression: Expression<'a>,
+        attribute: bool,
+    ) -> Expression<'a> {
+        match &mut expression {
+            Expression::UnaryExpression(unary)
+                if matches!(
+                    unary.operator,
+                    UnaryOperator::Typeof
+                        | UnaryOperator::Void
+                        | UnaryOperator::LogicalNot
+                        | UnaryOperator::UnaryNegation
+                        | UnaryOperator::UnaryPlus
+                ) =>
+            {
+                return expression
+            }
+            Expression::StringLiteral(_)
+            | Expression::NumericLiteral(_)
+            | Expression::FunctionExpression(_)
+            | Expression::ArrowFunctionExpression(_)
+            | Expression::JSXFragment(_) => return expression,
+            Expression::TemplateLiteral(literal) => {
+                for expression in literal.expressions.iter_mut() {
+                    let value = expression.take_in(jsx.allocator);
+                    *expression = self.escape_expression(jsx, value, attribute);
+                }
+                return expression;
+            }
+            Expression::BinaryExpression(binary) => {
+                let left = binary.left.take_in(jsx.allocator);
+                binary.left = self.escape_expression(jsx, left, attribute);
+                let right = binary.right.take_in(jsx.allocator);
+                binary.right = self.escape_expression(jsx, right, attribute);
+                return expression;
+            }
+            Expression::ConditionalExpression(conditional) => {
+                let consequent = conditional.consequent.take_in(jsx.allocator);
+                conditional.consequent = self.escape_expression(jsx, consequent, attribute);
+                let alternate = conditional.alternate.take_in(jsx.allocator);
+                conditional.alternate = self.escape_expression(jsx, alternate, attribute);
+                return expression;
+            }
+            Expression::LogicalExpression(logical) => {
+                let right = logical.right.take_in(jsx.allocator);
+                logical.right = self.escape_expression(jsx, right, attribute);
+                if logical.operator != LogicalOperator::And {
+                    let left = logical.left.take_in(jsx.allocator);
+                    logical.left = self.escape_expression(jsx, left, attribute);
+                }
+                return expression;
+            }
+            Expression::ParenthesizedExpression(parenthesized) => {
+                let inner = parenthesized.expression.take_in(jsx.allocator);
+                parenthesized.expression = self.escape_expression(jsx, inner, attribute);
+                return expression;
+            }
+            Expression::JSXElement(element) => {
+                let tag_name = AstUtils::get_element_name(element).unwrap_or_default();
+                if !AstUtils::is_component(&tag_name) {
+                    return expression;
+                }
+            }
+            _ => {}
+        }
+
+        jsx.register_import("escape");
+        let mut arguments = vec![expression];
+        if attribute {
+            arguments.push(
+                jsx.ast_builder
+                    .expression_boolean_literal(Span::default(), true),
+            );
+        }
+        jsx.create_call("_$escape", arguments)
+    }
+
+    /// Render an element with spreads as `_$ssrElement(tag, props, children, false)`
+    fn create_element<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &mut JSXElement<'a>,
+    ) -> Result<Expression<'a>, TransformError> {
+        let tag_name = AstUtils::get_element_name(element).unwrap_or_default();
+        let escape = !matches!(tag_name.as_str(), "script" | "style");
+
+        let mut segments = Vec::new();
+        let mut properties = jsx.ast_builder.vec();
+        for attr in element.opening_element.attributes.iter_mut() {
+            let attr = match attr {
+                JSXAttributeItem::SpreadAttribute(spread) => {
+                    if !properties.is_empty() {
+                        let properties = std::mem::replace(&mut properties, jsx.ast_builder.vec());
+                        segments.push(
+                            jsx.ast_builder
+                                .expression_object(Span::default(), properties),
+                        );
+                    }
+                    segments.push(spread.argument.take_in(jsx.allocator));
+                    continue;
+                }
+                JSXAttributeItem::Attribute(attr) => attr,
+            };
+            let name = Self::attribute_name(attr);
+            if name == "ref"
+                || name == "$ServerOnly"
+                || name.starts_with("use:")
+                || name.starts_with("prop:")
+                || EventTransformer::is_event_attribute(&name)
+            {
+                continue;
+            }
+            let Some(value) = self.take_value(jsx, attr) else {
+                continue;
+            };
+            if AstUtils::is_dynamic_expression(&value, true) {
+                let body = jsx.ast_builder.vec1(
+                    jsx.ast_builder
+                        .statement_return(Span::default(), Some(value)),
+                );
+                properties.push(jsx.create_getter(&name, body));
+            } else {
+                properties.push(jsx.create_property(&name, value));
+            }
+        }
+        if !properties.is_empty() || segments.is_empty() {
+            segments.push(
+                jsx.ast_builder
+                    .expression_object(Span::default(), properties),
+            );
+        }
+        let props = if segments.len() > 1 {
+            jsx.register_import("mergeProps");
+            jsx.create_call("_$mergeProps", segments)
+        } else {
+            segments.remove(0)
+        };
+
+        let mut children = Vec::new();
+        for child in element.children.iter_mut() {
+            if !JSXTransformer::is_significant_child(child) {
+                continue;
+            }
+            let value = match child {
+                JSXChild::Text(text) => {
+                    let raw = text.raw.unwrap_or(text.value);
+                    let text = TemplateGenerator::decode_html_entities(
+                        &TemplateGenerator::trim_whitespace(&raw),
+                    );
+                    jsx.ast_builder.expression_string_literal(
+                        Span::default(),
+                        jsx.ast_builder.atom(&text),
+                        None,
+                    )
+                }
+                JSXChild::Element(element) => jsx.transform_jsx_element(element)?,
+                JSXChild::ExpressionContainer(expr_container) => {
+                    let Some(expression) = jsx.take_expression(expr_container) else {
+                        continue;
+                    };
+                    if escape {
+                        self.escape_expression(jsx, expression, false)
+                    } else {
+                        expression
+                    }
+                }
+                JSXChild::Fragment(fragment) => {
+                    Expression::JSXFragment(fragment.take_in_box(jsx.allocator))
+                }
+                JSXChild::Spread(spread) => spread.expression.take_in(jsx.allocator),
+            };
+            children.push(value);
+        }
+        let children = match children.len() {
+            0 => jsx.create_identifier("undefined"),
+            1 => children.remove(0),
+            _ => jsx.ast_builder.expression_array(
+                Span::default(),
+                jsx.ast_builder
+                    .vec_from_iter(children.into_iter().map(ArrayExpressionElement::from)),
+            ),
+        };
+
+        jsx.register_import("ssrElement");
+        let tag_name = jsx.ast_builder.expression_string_literal(
+            Span::default(),
+            jsx.ast_builder.atom(&tag_name),
+            None,
+        );
+        let hydration_key = jsx
+            .ast_builder
+            .expression_boolean_literal(Span::default(), jsx.options.hydratable);
+        Ok(jsx.create_call(
+            "_$ssrElement",
+            vec![tag_name, props, children, hydration_key],
+        ))
+    }
+
+    /// Create `_$ssr(_tmpl$, ...values)`, reusing an identical template
+    fn create_template<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        template: SSRTemplate<'a>,
+    ) -> Expression<'a> {
+        let template_name = match jsx
+            .ssr_templates
+            .iter()
+            .find(|(_, parts)| **parts == template.template)
+        {
+            Some((name, _)) => name.clone(),
+            None => {
+                let name = jsx.get_next_template_name();
+                jsx.ssr_templates.insert(name.clone(), template.template);
+                name
+            }
+        };
+
+        jsx.register_import("ssr");
+        let mut arguments = vec![jsx.create_identifier(&template_name)];
+        arguments.extend(template.values);
+        jsx.create_call("_$ssr", arguments)
+    }
+
+    /// Take an attribute's value as an expression: `true` when absent
+    fn take_value<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        attr: &mut JSXAttribute<'a>,
+    ) -> Option<Expression<'a>> {
+        match &mut attr.value {
+            None => Some(
+                jsx.ast_builder
+                    .expression_boolean_literal(Span::default(), true),
+            ),
+            Some(JSXAttributeValue::StringLiteral(lit)) => Some(
+                jsx.create_string_literal(&TemplateGenerator::decode_html_entities(&lit.value)),
+            ),
+            Some(JSXAttributeValue::ExpressionContainer(expr_container)) => {
+                jsx.take_expression(expr_container)
+            }
+            Some(JSXAttributeValue::Element(element)) => {
+                Some(Expression::JSXElement(element.take_in_box(jsx.allocator)))
+            }
+            Some(JSXAttributeValue::Fragment(fragment)) => {
+                Some(Expression::JSXFragment(fragment.take_in_box(jsx.allocator)))
+            }
+        }
+    }
+
+    /// Attribute name as written, with any namespace prefix
+    fn attribute_name(attr: &JSXAttribute) -> String {
+        match &attr.name {
+            JSXAttributeName::Identifier(ident) => ident.name.to_string(),
+            JSXAttributeName::NamespacedName(name) => {
+                format!("{}:{}", name.namespace.name, name.name.name)
+            }
+        }
+    }
+}
+
+impl Default for SSRTransformer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssr_template_parts() {
+        let mut template = SSRTemplate::new();
+        template.push_static("<div>");
+        template.push_static("Hi");
+        assert_eq!(template.template, vec!["<div>Hi".to_string()]);
+
+        let allocator = oxc_allocator::Allocator::default();
+        let ast_builder = oxc_ast::AstBuilder::new(&allocator);
+        template.push_value(ast_builder.expression_null_literal(Span::default()));
+        template.push_static("</div>");
+        assert_eq!(
+            template.template,
+            vec!["<div>Hi".to_string(), "</div>".to_string()]
+        );
+        assert_eq!(template.values.len(), 1);
+    }
+
+    #[test]
+    fn test_normalize_style() {
+        assert_eq!(
+            SSRTransformer::normalize_style("color: red;\n    background-color: blue !important;"),
+            "color:red;background-color:blue !important;"
+        );
+    }
+}
@@ -0,0 +1,507 @@
+/// Universal (custom renderer) transformation for dom-expressions
+///
+/// With `GenerateMode::Universal` there are no HTML templates. Every native
+/// element is built imperatively through the renderer module:
+/// - `_$createElement("tag")` and `_$createTextNode(`text`)` create nodes
+/// - `_$insertNode(parent, node)` attaches static children
+/// - `_$setProp(el, "name", value)` applies every attribute, inside an effect
+///   when the value is reactive
+use crate::transformer::components::ComponentTransformer;
+use crate::transformer::jsx::{DynamicAttribute, JSXTransformer, TransformError};
+use crate::utils::ast_utils::AstUtils;
+use crate::utils::template::TemplateGenerator;
+use oxc_allocator::TakeIn;
+use oxc_ast::ast::{
+    BinaryOperator, Expression, JSXAttributeItem, JSXAttributeValue, JSXChild, JSXElement,
+    LogicalOperator, PropertyKind, Span, Statement, TemplateElementValue, VariableDeclarator,
+};
+
+/// A universal element under construction
+struct UniversalElement<'a> {
+    /// Variable holding the element
+    id: String,
+    /// `var` declarators creating this element and its referenced descendants
+    declarations: Vec<VariableDeclarator<'a>>,
+    /// Statements run once after the elements are created
+    exprs: Vec<Statement<'a>>,
+    /// Props re-applied in an effect whenever their inputs change
+    dynamics: Vec<DynamicAttribute<'a>>,
+}
+
+/// A lowered child of a universal element
+enum UniversalNode<'a> {
+    Element(UniversalElement<'a>),
+    Text { id: String, text: String },
+    Insertion(Expression<'a>),
+}
+
+impl UniversalNode<'_> {
+    fn id(&self) -> Option<&str> {
+        match self {
+            UniversalNode::Element(element) => Some(&element.id),
+            UniversalNode::Text { id, .. } => Some(id),
+            UniversalNode::Insertion(_) => None,
+        }
+    }
+}
+
+pub struct UniversalTransformer;
+
+impl UniversalTransformer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Transform a native element into an IIFE that builds it node by node
+    ///
+    /// An element with no children, props or effects is just `_$createElement("tag")`.
+    pub fn transform_element<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &mut JSXElement<'a>,
+    ) -> Result<Expression<'a>, TransformError> {
+        let mut result = self.transform_native_element(jsx, element)?;
+        let effect = self.wrap_dynamics(jsx, std::mem::take(&mut result.dynamics));
+
+        if result.declarations.len() == 1 && result.exprs.is_empty() && effect.is_none() {
+            if let Some(init) = result.declarations.remove(0).init {
+                return Ok(init);
+            }
+        }
+
+        let mut statements = jsx.ast_builder.vec();
+        statements.push(jsx.create_var_declaration(result.declarations));
+        statements.extend(result.exprs);
+        statements.extend(effect);
+        statements.push(
+            jsx.ast_builder
+                .statement_return(Span::default(), Some(jsx.create_identifier(&result.id))),
+        );
+        Ok(jsx.create_iife(statements))
+    }
+
+    /// Create an element, apply its props and build its children
+    fn transform_native_element<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &mut JSXElement<'a>,
+    ) -> Result<UniversalElement<'a>, TransformError> {
+        let tag_name = AstUtils::get_element_name(element).unwrap_or_default();
+        let id = jsx.generate_uid("el$");
+        jsx.register_import("createElement");
+        let tag = jsx.ast_builder.expression_string_literal(
+            Span::default(),
+            jsx.ast_builder.atom(&tag_name),
+            None,
+        );
+        let create = jsx.create_call("_$createElement", vec![tag]);
+        let mut result = UniversalElement {
+            declarations: vec![jsx.create_declarator(&id, create)],
+            id,
+            exprs: Vec::new(),
+            dynamics: Vec::new(),
+        };
+
+        let children_prop = self.transform_attributes(jsx, element, &mut result);
+        self.transform_children(jsx, &mut element.children, children_prop, &mut result)?;
+        Ok(result)
+    }
+
+    /// Apply every attribute through `_$setProp`
+    ///
+    /// Returns the `children` prop, which stands in for missing JSX children.
+    fn transform_attributes<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &mut JSXElement<'a>,
+        result: &mut UniversalElement<'a>,
+    ) -> Option<Expression<'a>> {
+        let attributes = &mut element.opening_element.attributes;
+        let mut handled = vec![false; attributes.len()];
+        let mut spread = None;
+        if attributes
+            .iter()
+            .any(|attr| matches!(attr, JSXAttributeItem::SpreadAttribute(_)))
+        {
+            let has_children = !element.children.is_empty();
+            spread = jsx.transform_spread_attributes(
+                attributes,
+                &mut handled,
+                Some(&result.id),
+                false,
+                has_children,
+            );
+        }
+
+        let mut children = None;
+        for (attr, handled) in attributes.iter_mut().zip(handled) {
+            let JSXAttributeItem::Attribute(attr) = attr else {
+                continue;
+            };
+            if handled {
+                continue;
+            }
+            let name = JSXTransformer::attribute_name(&attr.name);
+            let value = match &mut attr.value {
+                None => jsx
+                    .ast_builder
+                    .expression_boolean_literal(Span::default(), true),
+                Some(JSXAttributeValue::StringLiteral(lit)) => {
+                    jsx.create_string_literal(&TemplateGenerator::decode_html_entities(&lit.value))
+                }
+                Some(JSXAttributeValue::ExpressionContainer(expr_container)) => {
+                    let Some(expression) = jsx.take_expression(expr_container) else {
+                        continue;
+                    };
+                    if name == "children" {
+                        children = Some(expression);
+                        continue;
+                    }
+                    if jsx.options.effect_wrapper
+                        && AstUtils::is_dynamic_expression(&expression, true)
+                    {
+                        result.dynamics.push(DynamicAttribute {
+                            name,
+                            element: result.id.clone(),
+                            expression,
+                            requires_effect: true,
+                        });
+                        continue;
+                    }
+                    expression
+                }
+                Some(JSXAttributeValue::Element(element)) => {
+                    Expression::JSXElement(element.take_in_box(jsx.allocator))
+                }
+                Some(JSXAttributeValue::Fragment(fragment)) => {
+                    Expression::JSXFragment(fragment.take_in_box(jsx.allocator))
+                }
+            };
+            let set_prop = self.create_set_prop(jsx, &result.id, &name, value, None);
+            result.exprs.push(
+                jsx.ast_builder
+                    .statement_expression(Span::default(), set_prop),
+            );
+        }
+        result.exprs.extend(spread);
+        children
+    }
+
+    /// Build the children, attaching static nodes ahead of the element's other code
+    ///
+    /// Mirrors dom-expressions' universal `transformChildren`: text becomes
+    /// `_$createTextNode`, nested elements are appended with `_$insertNode`,
+    /// and expressions go through `_$insert` before the next static node.
+    fn transform_children<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        children: &mut oxc_allocator::Vec<'a, JSXChild<'a>>,
+        children_prop: Option<Expression<'a>>,
+        result: &mut UniversalElement<'a>,
+    ) -> Result<(), TransformError> {
+        let filtered: Vec<usize> = (0..children.len())
+            .filter(|&index| JSXTransformer::is_significant_child(&children[index]))
+            .collect();
+        let multi = {
+            let significant: Vec<&JSXChild<'a>> =
+                filtered.iter().map(|&index| &children[index]).collect();
+            JSXTransformer::check_length(&significant)
+        };
+
+        let mut nodes = Vec::new();
+        for &index in &filtered {
+            let Some(node) = self.transform_node(jsx, &mut children[index])? else {
+                continue;
+            };
+            if let (
+                UniversalNode::Text { text, .. },
+                Some(UniversalNode::Text { text: previous, .. }),
+            ) = (&node, nodes.last_mut())
+            {
+                previous.push_str(text);
+                continue;
+            }
+            nodes.push(node);
+        }
+        if nodes.is_empty() {
+            if let Some(children_prop) = children_prop {
+                nodes.push(UniversalNode::Insertion(
+                    jsx.create_insert_value(children_prop),
+                ));
+            }
+        }
+
+        let next_ids: Vec<Option<String>> = (0..nodes.len())
+            .map(|index| {
+                nodes[index + 1..]
+                    .iter()
+                    .find_map(|node| node.id().map(str::to_string))
+            })
+            .collect();
+
+        let mut appends = Vec::new();
+        let mut child_exprs = Vec::new();
+        for (node, next_id) in nodes.into_iter().zip(next_ids) {
+            match node {
+                UniversalNode::Element(child) => {
+                    let insert_node =
+                        self.create_insert_node(jsx, &result.id, jsx.create_identifier(&child.id));
+                    result.declarations.extend(child.declarations);
+                    child_exprs.extend(child.exprs);
+                    result.dynamics.extend(child.dynamics);
+                    appends.push(
+                        jsx.ast_builder
+                            .statement_expression(Span::default(), insert_node),
+                    );
+                }
+                UniversalNode::Text { id, text } => {
+                    let text_node = self.create_text_node(jsx, &text);
+                    let node = if multi {
+                        result
+                            .declarations
+                            .push(jsx.create_declarator(&id, text_node));
+                        jsx.create_identifier(&id)
+                    } else {
+                        text_node
+                    };
+                    let insert_node = self.create_insert_node(jsx, &result.id, node);
+                    appends.push(
+                        jsx.ast_builder
+                            .statement_expression(Span::default(), insert_node),
+                    );
+                }
+                UniversalNode::Insertion(expression) => {
+                    let insert = jsx.register_import("insert");
+                    let mut arguments = vec![jsx.create_identifier(&result.id), expression];
+                    if multi {
+                        arguments.push(match next_id {
+                            Some(next_id) => jsx.create_identifier(&next_id),
+                            None => jsx.ast_builder.expression_null_literal(Span::default()),
+                        });
+                    }
+                    let insert = jsx.create_call(&insert, arguments);
+                    child_exprs.push(
+                        jsx.ast_builder
+                            .statement_expression(Span::default(), insert),
+                    );
+                }
+            }
+        }
+
+        result.exprs.extend(child_exprs);
+        result.exprs.splice(0..0, appends);
+        Ok(())
+    }
+
+    /// Lower a single child; `None` for children that render nothing
+    fn transform_node<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        child: &mut JSXChild<'a>,
+    ) -> Result<Option<UniversalNode<'a>>, TransformError> {
+        match child {
+            JSXChild::Element(element) => {
+                let tag_name = AstUtils::get_element_name(element).unwrap_or_default();
+                if AstUtils::is_component(&tag_name) {
+                    let component =
+                        ComponentTransformer::new().transform_custom_component(jsx, element)?;
+                    return Ok(Some(UniversalNode::Insertion(component)));
+                }
+                if jsx.is_foreign_element(&tag_name) {
+                    let foreign = jsx.transform_jsx_element(element)?;
+                    return Ok(Some(UniversalNode::Insertion(foreign)));
+                }
+                Ok(Some(UniversalNode::Element(
+                    self.transform_native_element(jsx, element)?,
+                )))
+            }
+            JSXChild::Text(text) => {
+                let raw = text.raw.unwrap_or(text.value);
+                let text = TemplateGenerator::decode_html_entities(
+                    &TemplateGenerator::trim_whitespace(&raw),
+                );
+                if text.is_empty() {
+                    return Ok(None);
+                }
+                let id = jsx.generate_uid("el$");
+                Ok(Some(UniversalNode::Text { id, text }))
+            }
+            JSXChild::ExpressionContainer(expr_container) => {
+                let static_value = expr_container
+                    .expression
+                    .as_expression()
+                    .and_then(AstUtils::get_static_value);
+                if let Some(text) = static_value {
+                    let id = jsx.generate_uid("el$");
+                    return Ok(Some(UniversalNode::Text { id, text }));
+                }
+                let Some(expression) = jsx.take_expression(expr_container) else {
+                    return Ok(None);
+                };
+                Ok(Some(UniversalNode::Insertion(
+                    jsx.create_insert_value(expression),
+                )))
+            }
+            JSXChild::Fragment(fragment) => Ok(Some(UniversalNode::Insertion(
+                Expression::JSXFragment(fragment.take_in_box(jsx.allocator)),
+            ))),
+            JSXChild::Spread(spread) => {
+                let expression = spread.expression.take_in(jsx.allocator);
+                Ok(Some(UniversalNode::Insertion(
+                    jsx.create_insert_value(expression),
+                )))
+            }
+        }
+    }
+
+    /// Create `_$insertNode(parent, node)`
+    fn create_insert_node<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        parent: &str,
+        node: Expression<'a>,
+    ) -> Expression<'a> {
+        jsx.register_import("insertNode");
+        jsx.create_call("_$insertNode", vec![jsx.create_identifier(parent), node])
+    }
+
+    /// Create ``_$createTextNode(`text`)``
+    fn create_text_node<'a>(&self, jsx: &mut JSXTransformer<'a>, text: &str) -> Expression<'a> {
+        jsx.register_import("createTextNode");
+        let raw = TemplateGenerator::escape_template_literal(text);
+        let quasi = jsx.ast_builder.template_element(
+            Span::default(),
+            TemplateElementValue {
+                raw: jsx.ast_builder.atom(&raw),
+                cooked: Some(jsx.ast_builder.atom(text)),
+            },
+            true,
+        );
+        let literal = jsx.ast_builder.expression_template_literal(
+            Span::default(),
+            jsx.ast_builder.vec1(quasi),
+            jsx.ast_builder.vec(),
+        );
+        jsx.create_call("_$createTextNode", vec![literal])
+    }
+
+    /// Create `_$setProp(el, "name", value[, prev])`
+    fn create_set_prop<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        element: &str,
+        name: &str,
+        value: Expression<'a>,
+        prev: Option<Expression<'a>>,
+    ) -> Expression<'a> {
+        jsx.register_import("setProp");
+        let name = jsx.ast_builder.expression_string_literal(
+            Span::default(),
+            jsx.ast_builder.atom(name),
+            None,
+        );
+        let mut arguments = vec![jsx.create_identifier(element), name, value];
+        arguments.extend(prev);
+        jsx.create_call("_$setProp", arguments)
+    }
+
+    /// Re-apply reactive props in a single effect
+    ///
+    /// `setProp` returns the applied value, so it is stored as the previous
+    /// value: `_$effect(_$p => _$setProp(el, "name", value, _$p))`, or for
+    /// several props `_v$ !== _p$.e && (_p$.e = _$setProp(el, "name", _v$, _p$.e))`.
+    fn wrap_dynamics<'a>(
+        &self,
+        jsx: &mut JSXTransformer<'a>,
+        mut dynamics: Vec<DynamicAttribute<'a>>,
+    ) -> Option<Statement<'a>> {
+        if dynamics.is_empty() {
+            return None;
+        }
+        jsx.register_import("effect");
+
+        if dynamics.len() == 1 {
+            let attr = dynamics.remove(0);
+            let prev = jsx.create_identifier("_$p");
+            let set_prop =
+                self.create_set_prop(jsx, &attr.element, &attr.name, attr.expression, Some(prev));
+            let effect = jsx.create_call("_$effect", vec![jsx.create_arrow(&["_$p"], set_prop)]);
+            return Some(
+                jsx.ast_builder
+                    .statement_expression(Span::default(), effect),
+            );
+        }
+
+        let mut declarators = Vec::new();
+        let mut statements = jsx.ast_builder.vec();
+        let mut properties = jsx.ast_builder.vec();
+        for (index, attr) in dynamics.into_iter().enumerate() {
+            let key = JSXTransformer::effect_key(index);
+            let value_id = jsx.generate_uid("v$");
+            declarators.push(jsx.create_declarator(&value_id, attr.expression));
+            properties.push(
+                jsx.ast_builder.object_property_kind_object_property(
+                    Span::default(),
+                    PropertyKind::Init,
+                    jsx.ast_builder.property_key_static_identifier(
+                        Span::default(),
+                        jsx.ast_builder.atom(&key),
+                    ),
+                    jsx.create_identifier("undefined"),
+                    false,
+                    false,
+                    false,
+                ),
+            );
+
+            let previous = format!("_p$.{}", key);
+            let set_prop = self.create_set_prop(
+                jsx,
+                &attr.element,
+                &attr.name,
+                jsx.create_identifier(&value_id),
+                Some(jsx.create_member_path(&previous)),
+            );
+            let changed = jsx.ast_builder.expression_binary(
+                Span::default(),
+                jsx.create_identifier(&value_id),
+                BinaryOperator::StrictInequality,
+                jsx.create_member_path(&previous),
+            );
+            let statement = jsx.ast_builder.expression_logical(
+                Span::default(),
+                changed,
+                LogicalOperator::And,
+                jsx.create_assignment(&previous, set_prop),
+            );
+            statements.push(
+                jsx.ast_builder
+                    .statement_expression(Span::default(), statement),
+            );
+        }
+
+        statements.insert(0, jsx.create_var_declaration(declarators));
+        statements.push(
+            jsx.ast_builder
+                .statement_return(Span::default(), Some(jsx.create_identifier("_p$"))),
+        );
+        let effect = jsx.create_call(
+            "_$effect",
+            vec![
+                jsx.create_block_arrow(&["_p$"], statements),
+                jsx.ast_builder
+                    .expression_object(Span::default(), properties),
+            ],
+        );
+        Some(
+            jsx.ast_builder
+                .statement_expression(Span::default(), effect),
+        )
+    }
+}
+
+impl Default for UniversalTransformer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
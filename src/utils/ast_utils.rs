@@ -1,11 +1,16 @@
 /// AST utility functions for working with OXC AST nodes
-/// 
+///
 /// This module provides helper functions for:
 /// - Creating and manipulating AST nodes
 /// - Traversing AST structures
 /// - Common transformations
-
-use oxc_ast::ast::JSXElement;
+use oxc_ast::ast::{
+    ArrowFunctionExpression, CallExpression, Expression, Function, JSXElement, JSXElementName,
+    JSXFragment, JSXMemberExpression, JSXMemberExpressionObject, MemberExpression,
+    TaggedTemplateExpression,
+};
+use oxc_ast_visit::Visit;
+use oxc_syntax::scope::ScopeFlags;
 
 /// Helper functions for JSX element manipulation
 pub struct AstUtils;
@@ -26,9 +31,56 @@ impl AstUtils {
     }
 
     /// Extract the tag name from a JSX element
-    pub fn get_element_name(_element: &JSXElement) -> Option<String> {
-        // TODO: Implement element name extraction
-        None
+    ///
+    /// Namespaced names keep their prefix (`svg:rect`) and member expressions
+    /// are joined with dots (`Foo.Bar`).
+    pub fn get_element_name(element: &JSXElement) -> Option<String> {
+        Some(match &element.opening_element.name {
+            JSXElementName::Identifier(ident) => ident.name.to_string(),
+            JSXElementName::IdentifierReference(ident) => ident.name.to_string(),
+            JSXElementName::NamespacedName(name) => {
+                format!("{}:{}", name.namespace.name, name.name.name)
+            }
+            JSXElementName::MemberExpression(member) => Self::jsx_member_name(member),
+            JSXElementName::ThisExpression(_) => "this".to_string(),
+        })
+    }
+
+    fn jsx_member_name(member: &JSXMemberExpression) -> String {
+        let object = match &member.object {
+            JSXMemberExpressionObject::IdentifierReference(ident) => ident.name.to_string(),
+            JSXMemberExpressionObject::MemberExpression(inner) => Self::jsx_member_name(inner),
+            JSXMemberExpressionObject::ThisExpression(_) => "this".to_string(),
+        };
+        format!("{}.{}", object, member.property.name)
+    }
+
+    /// Check if a tag name refers to a component rather than a native element
+    ///
+    /// Like dom-expressions, anything capitalized, dotted or not starting with a
+    /// letter is a component.
+    pub fn is_component(tag_name: &str) -> bool {
+        tag_name.chars().next().is_some_and(|first| {
+            first.is_uppercase() || !first.is_ascii_alphabetic() || tag_name.contains('.')
+        })
+    }
+
+    /// Evaluate an expression that is a compile-time string or number
+    ///
+    /// Returns the value as it would be stringified, so it can be written
+    /// straight into template HTML.
+    pub fn get_static_value(expr: &Expression) -> Option<String> {
+        match expr {
+            Expression::StringLiteral(lit) => Some(lit.value.to_string()),
+            Expression::NumericLiteral(lit) => Some(lit.value.to_string()),
+            Expression::TemplateLiteral(lit) if lit.expressions.is_empty() => lit
+                .quasis
+                .first()
+                .and_then(|quasi| quasi.value.cooked)
+                .map(|cooked| cooked.to_string()),
+            Expression::ParenthesizedExpression(paren) => Self::get_static_value(&paren.expression),
+            _ => None,
+        }
     }
 
     /// Check if a JSX element is self-closing
@@ -36,10 +88,145 @@ impl AstUtils {
         // TODO: Implement self-closing detection
         false
     }
+
+    /// Check if an expression may read reactive state and must be re-evaluated
+    ///
+    /// Mirrors dom-expressions' `isDynamic`: function values are never dynamic,
+    /// calls and tagged templates always are, and member access only counts when
+    /// `check_member` is set. Nested functions are not descended into.
+    pub fn is_dynamic_expression(expr: &Expression, check_member: bool) -> bool {
+        Self::is_dynamic_expression_with_tags(expr, check_member, false)
+    }
+
+    /// Like [`AstUtils::is_dynamic_expression`], optionally counting JSX as dynamic
+    ///
+    /// Component props use `check_tags` so JSX values become getters and are only
+    /// created when read. Without it, JSX is not descended into.
+    pub fn is_dynamic_expression_with_tags(
+        expr: &Expression,
+        check_member: bool,
+        check_tags: bool,
+    ) -> bool {
+        if matches!(
+            expr,
+            Expression::ArrowFunctionExpression(_) | Expression::FunctionExpression(_)
+        ) {
+            return false;
+        }
+
+        let mut checker = DynamicExpressionChecker {
+            check_member,
+            check_tags,
+            dynamic: false,
+        };
+        checker.visit_expression(expr);
+        checker.dynamic
+    }
+}
+
+/// Visitor backing [`AstUtils::is_dynamic_expression`]
+struct DynamicExpressionChecker {
+    check_member: bool,
+    check_tags: bool,
+    dynamic: bool,
+}
+
+impl<'a> Visit<'a> for DynamicExpressionChecker {
+    fn visit_call_expression(&mut self, _it: &CallExpression<'a>) {
+        self.dynamic = true;
+    }
+
+    fn visit_tagged_template_expression(&mut self, _it: &TaggedTemplateExpression<'a>) {
+        self.dynamic = true;
+    }
+
+    fn visit_member_expression(&mut self, it: &MemberExpression<'a>) {
+        if self.check_member {
+            self.dynamic = true;
+        } else {
+            oxc_ast_visit::walk::walk_member_expression(self, it);
+        }
+    }
+
+    fn visit_jsx_element(&mut self, _it: &JSXElement<'a>) {
+        self.dynamic |= self.check_tags;
+    }
+
+    fn visit_jsx_fragment(&mut self, it: &JSXFragment<'a>) {
+        self.dynamic |= self.check_tags && !it.children.is_empty();
+    }
+
+    fn visit_function(&mut self, _it: &Function<'a>, _flags: ScopeFlags) {}
+
+    fn visit_arrow_function_expression(&mut self, _it: &ArrowFunctionExpression<'a>) {}
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use oxc_allocator::Allocator;
+    use oxc_span::SourceType;
+
+    fn is_dynamic(source: &str, check_member: bool) -> bool {
+        let allocator = Allocator::default();
+        let expr =
+            oxc_parser::Parser::new(&allocator, source, SourceType::default().with_jsx(true))
+                .parse_expression()
+                .unwrap();
+        AstUtils::is_dynamic_expression(&expr, check_member)
+    }
+
+    #[test]
+    fn test_is_dynamic_expression() {
+        assert!(is_dynamic("count()", false));
+        assert!(is_dynamic("a + b()", false));
+        assert!(is_dynamic("props.name", true));
+        assert!(!is_dynamic("props.name", false));
+        assert!(!is_dynamic("name", true));
+        assert!(!is_dynamic("() => count()", true));
+        assert!(!is_dynamic("\"static\"", true));
+        assert!(!is_dynamic("<div title={a()} />", true));
+    }
+
+    #[test]
+    fn test_is_dynamic_expression_with_tags() {
+        let allocator = Allocator::default();
+        let source_type = SourceType::default().with_jsx(true);
+        let expr = oxc_parser::Parser::new(&allocator, "<Loading />", source_type)
+            .parse_expression()
+            .unwrap();
+        assert!(AstUtils::is_dynamic_expression_with_tags(&expr, true, true));
+        assert!(!AstUtils::is_dynamic_expression_with_tags(
+            &expr, true, false
+        ));
+    }
+
+    #[test]
+    fn test_get_static_value() {
+        let allocator = Allocator::default();
+        let value = |source: &str| {
+            let expr = oxc_parser::Parser::new(&allocator, source, SourceType::default())
+                .parse_expression()
+                .unwrap();
+            AstUtils::get_static_value(&expr)
+        };
+
+        assert_eq!(value("\"entry\""), Some("entry".to_string()));
+        assert_eq!(value("12"), Some("12".to_string()));
+        assert_eq!(value("`plain`"), Some("plain".to_string()));
+        assert_eq!(value("name"), None);
+        assert_eq!(value("`${a}`"), None);
+    }
+
+    #[test]
+    fn test_is_component() {
+        assert!(AstUtils::is_component("Show"));
+        assert!(AstUtils::is_component("ui.button"));
+        assert!(AstUtils::is_component("_Private"));
+        assert!(!AstUtils::is_component("div"));
+        assert!(!AstUtils::is_component("my-element"));
+    }
+
     #[test]
     fn test_ast_utils() {
         // Placeholder tests - these will be implemented later
@@ -1,4 +1,5 @@
 pub mod ast_utils;
+pub mod entities;
 pub mod template;
 
 // Re-export when needed
@@ -1,64 +1,130 @@
 /// Template generation utilities for dom-expressions
 ///
-/// This module handles the generation of template strings and
-/// template function calls for dom-expressions JSX transformation
-use oxc_ast::ast::{JSXChild, JSXElement};
+/// This module handles the escaping, entity decoding and whitespace
+/// rules that template HTML and text children go through
+use crate::utils::entities::NAMED_ENTITIES;
 
 /// Template generator for converting JSX to dom-expressions templates
-pub struct TemplateGenerator {
-    /// Counter for generating unique template names
-    template_counter: usize,
-}
+pub struct TemplateGenerator;
 
 impl TemplateGenerator {
-    pub fn new() -> Self {
-        Self {
-            template_counter: 0,
+    /// Escape template HTML for use as the raw text of a JavaScript template literal
+    ///
+    /// Follows dom-expressions' `escapeStringForTemplate`: every `{` is escaped so
+    /// `${` can never start a substitution, along with backticks, backslashes and
+    /// characters that cannot appear raw in the output.
+    pub fn escape_template_literal(input: &str) -> String {
+        let mut escaped = String::with_capacity(input.len());
+        for ch in input.chars() {
+            match ch {
+                '{' => escaped.push_str("\\{"),
+                '`' => escaped.push_str("\\`"),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\t' => escaped.push_str("\\t"),
+                '\u{8}' => escaped.push_str("\\b"),
+                '\u{c}' => escaped.push_str("\\f"),
+                '\u{b}' => escaped.push_str("\\v"),
+                '\r' => escaped.push_str("\\r"),
+                '\u{2028}' => escaped.push_str("\\u2028"),
+                '\u{2029}' => escaped.push_str("\\u2029"),
+                _ => escaped.push(ch),
+            }
         }
+        escaped
     }
 
-    /// Generate a template string from JSX element
-    pub fn generate_template_string(&self, _element: &JSXElement) -> String {
-        // TODO: Implement template string generation
-        // This will convert JSX elements to HTML template strings
-        // Example: <div class="test">content</div> -> "<div class=\"test\">content</div>"
-        String::new()
-    }
-
-    /// Generate template function call
-    pub fn generate_template_call(&mut self, _element: &JSXElement) -> String {
-        // TODO: Implement template function call generation
-        // This will create calls like: _tmpl$1()
-        self.template_counter += 1;
-        format!("_tmpl${}()", self.template_counter)
+    /// Escape text for inclusion in template HTML
+    ///
+    /// Mirrors dom-expressions' `escapeHTML`: `&` is always escaped, plus `"` inside
+    /// attribute values or `<` inside text content.
+    pub fn escape_html(input: &str, attribute: bool) -> String {
+        let (delimiter, escaped_delimiter) = if attribute {
+            ('"', "&quot;")
+        } else {
+            ('<', "&lt;")
+        };
+        let mut escaped = String::with_capacity(input.len());
+        for ch in input.chars() {
+            match ch {
+                '&' => escaped.push_str("&amp;"),
+                ch if ch == delimiter => escaped.push_str(escaped_delimiter),
+                _ => escaped.push(ch),
+            }
+        }
+        escaped
     }
 
-    /// Generate unique template variable name
-    pub fn get_next_template_name(&mut self) -> String {
-        self.template_counter += 1;
-        format!("_tmpl${}", self.template_counter)
+    /// Decode HTML character references in JSX text
+    ///
+    /// Component children are passed as JavaScript strings rather than template
+    /// HTML, so `&nbsp;` has to become `\u{a0}` first. Unknown references are kept.
+    pub fn decode_html_entities(input: &str) -> String {
+        let mut decoded = String::with_capacity(input.len());
+        let mut rest = input;
+        while let Some(start) = rest.find('&') {
+            decoded.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let entity = rest[1..].find(';').and_then(|end| {
+                let name = &rest[1..end + 1];
+                let value = match NAMED_ENTITIES.binary_search_by(|(entity, _)| entity.cmp(&name)) {
+                    Ok(index) => Some(NAMED_ENTITIES[index].1.to_string()),
+                    Err(_) => name
+                        .strip_prefix("#x")
+                        .or_else(|| name.strip_prefix("#X"))
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .or_else(|| name.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                        .and_then(char::from_u32)
+                        .map(String::from),
+                };
+                value.map(|value| (value, end + 2))
+            });
+            match entity {
+                Some((value, len)) => {
+                    decoded.push_str(&value);
+                    rest = &rest[len..];
+                }
+                None => {
+                    decoded.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        decoded.push_str(rest);
+        decoded
     }
 
-    /// Convert JSX children to template content
-    pub fn children_to_template(&self, _children: &[JSXChild]) -> String {
-        // TODO: Implement children to template conversion
-        String::new()
-    }
+    /// Collapse JSX text whitespace the way JSX does
+    ///
+    /// Lines are trimmed where they meet a line break, whitespace-only lines are
+    /// dropped and the remaining lines are joined by a single space.
+    pub fn trim_whitespace(text: &str) -> String {
+        let text = text.replace('\r', "");
+        let text = if text.contains('\n') {
+            text.split('\n')
+                .enumerate()
+                .map(|(index, line)| if index == 0 { line } else { line.trim_start() })
+                .filter(|line| !line.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            text
+        };
 
-    /// Escape template string for JavaScript
-    pub fn escape_template_string(input: &str) -> String {
-        input
-            .replace('\\', "\\\\")
-            .replace('"', "\\\"")
-            .replace('\n', "\\n")
-            .replace('\r', "\\r")
-            .replace('\t', "\\t")
-    }
-}
-
-impl Default for TemplateGenerator {
-    fn default() -> Self {
-        Self::new()
+        let mut collapsed = String::with_capacity(text.len());
+        let mut in_whitespace = false;
+        for ch in text.chars() {
+            if ch.is_whitespace() {
+                if !in_whitespace {
+                    collapsed.push(' ');
+                }
+                in_whitespace = true;
+            } else {
+                collapsed.push(ch);
+                in_whitespace = false;
+            }
+        }
+        collapsed
     }
 }
 
@@ -67,21 +133,55 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_template_generator() {
-        let mut generator = TemplateGenerator::new();
-        assert_eq!(generator.get_next_template_name(), "_tmpl$1");
-        assert_eq!(generator.get_next_template_name(), "_tmpl$2");
+    fn test_escape_template_literal() {
+        assert_eq!(
+            TemplateGenerator::escape_template_literal("<p>${blah}</p>"),
+            "<p>$\\{blah}</p>"
+        );
+        assert_eq!(
+            TemplateGenerator::escape_template_literal("<div title=\"`a`\">\\\n"),
+            "<div title=\"\\`a\\`\">\\\\\\n"
+        );
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            TemplateGenerator::escape_html("<b>a & \"b\"</b>", false),
+            "&lt;b>a &amp; \"b\"&lt;/b>"
+        );
+        assert_eq!(
+            TemplateGenerator::escape_html("<u>\"hi\"</u>", true),
+            "<u>&quot;hi&quot;</u>"
+        );
     }
 
     #[test]
-    fn test_escape_template_string() {
+    fn test_decode_html_entities() {
+        assert_eq!(
+            TemplateGenerator::decode_html_entities("&nbsp;&lt;Hi&gt;&#65;&#x42; & co&unknown;"),
+            "\u{a0}<Hi>AB & co&unknown;"
+        );
+        assert_eq!(
+            TemplateGenerator::decode_html_entities("&times; &rarr; &Aacute; &bne;"),
+            "\u{d7} \u{2192} \u{c1} =\u{20e5}"
+        );
+    }
+
+    #[test]
+    fn test_trim_whitespace() {
+        assert_eq!(
+            TemplateGenerator::trim_whitespace("Hello  World"),
+            "Hello World"
+        );
         assert_eq!(
-            TemplateGenerator::escape_template_string("hello \"world\""),
-            "hello \\\"world\\\""
+            TemplateGenerator::trim_whitespace("\n      Welcome\n    "),
+            "Welcome"
         );
         assert_eq!(
-            TemplateGenerator::escape_template_string("line1\nline2"),
-            "line1\\nline2"
+            TemplateGenerator::trim_whitespace("Hello\n      friend "),
+            "Hello friend "
         );
+        assert_eq!(TemplateGenerator::trim_whitespace("\n   \n  "), "");
     }
 }